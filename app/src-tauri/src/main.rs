@@ -3,21 +3,64 @@
     windows_subsystem = "windows"
 )]
 use notify::Watcher;
-use std::{io::Write, sync::atomic::AtomicBool};
+use std::io::Write;
 use tauri::Manager;
 
 const PREFERENCES_FILE_NAME: &str = "preferences.json";
+const LOG_FILE_NAME: &str = "undr.log";
+const LOG_ROTATED_FILE_NAME: &str = "undr.log.1";
+// the log file is moved aside at startup once it has grown past this size (see `open_log_file`);
+// it is not rotated again while a session is running
+const LOG_ROTATE_MAX_BYTES: u64 = 1 << 20; // 1 MiB
+// bursts of filesystem events coalesce into a single callback once this much time has passed
+// without a new one, so an install touching thousands of files doesn't flood the frontend with
+// one `dataset_status` event per file
+const DATASETS_WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DatasetStatus {
+    name: String,
+    file_count: u64,
+}
+
+fn emit_dataset_status(app_handle: &tauri::AppHandle, statuses: Vec<DatasetStatus>) {
+    if statuses.is_empty() {
+        return;
+    }
+    if let Err(emit_error) = app_handle.emit_to("main", "dataset_status", statuses) {
+        log::warn!("emit_to('main', 'dataset_status', payload) failed with error {emit_error:?}");
+    }
+}
+
+/// Recursively counts the regular files under `directory`, used to tell whether a dataset gained
+/// or lost files between two debounced watch callbacks. A missing or unreadable directory (a
+/// dataset that has not been installed yet) simply counts as empty.
+fn count_files(directory: &std::path::Path) -> u64 {
+    let mut count = 0;
+    if let Ok(entries) = std::fs::read_dir(directory) {
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => count += count_files(&entry.path()),
+                Ok(file_type) if file_type.is_file() => count += 1,
+                _ => (),
+            }
+        }
+    }
+    count
+}
 
 struct State {
     watcher: Option<notify::RecommendedWatcher>,
     path: Option<std::path::PathBuf>,
+    datasets_watcher: Option<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>>,
+    dataset_file_counts: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, u64>>>,
 }
 
 impl State {
     fn watch(&mut self, path: std::path::PathBuf) {
         if let Some(watcher) = self.watcher.as_mut() {
             if let Err(error) = watcher.watch(&path, notify::RecursiveMode::NonRecursive) {
-                eprintln!("watch error {error:?}");
+                log::warn!("watch error {error:?}");
             }
         }
         self.path.replace(path);
@@ -27,17 +70,81 @@ impl State {
         if let Some(path) = self.path.as_mut() {
             if let Some(watcher) = self.watcher.as_mut() {
                 if let Err(error) = watcher.unwatch(path) {
-                    eprintln!("watch error {error:?}");
+                    log::warn!("watch error {error:?}");
                 }
             }
             self.path.take();
         }
     }
+
+    /// Tears down any watch left over from a previous configuration and starts a fresh
+    /// recursive, debounced watch on `configuration`'s (already resolved) datasets directory, so
+    /// install-state changes made outside this process (a concurrent CLI install, a manual
+    /// `rm`, ...) still reach the frontend while an action is not running here.
+    fn watch_datasets(&mut self, app_handle: tauri::AppHandle, configuration: &undr::Configuration) {
+        self.unwatch_datasets();
+        let directory = configuration.directory.clone();
+        {
+            let mut counts = self.dataset_file_counts.lock().unwrap();
+            for dataset in &configuration.datasets {
+                counts.insert(dataset.name.0.clone(), count_files(&directory.join(&dataset.name.0)));
+            }
+        }
+        let dataset_file_counts = self.dataset_file_counts.clone();
+        let watch_directory = directory.clone();
+        match notify_debouncer_mini::new_debouncer(
+            DATASETS_WATCH_DEBOUNCE,
+            move |result: notify_debouncer_mini::DebounceEventResult| match result {
+                Ok(events) => {
+                    let mut touched_names = std::collections::HashSet::new();
+                    for event in &events {
+                        if let Ok(relative) = event.path.strip_prefix(&watch_directory) {
+                            if let Some(first) = relative.components().next() {
+                                touched_names.insert(first.as_os_str().to_string_lossy().into_owned());
+                            }
+                        }
+                    }
+                    let mut counts = dataset_file_counts.lock().unwrap();
+                    let mut statuses = Vec::new();
+                    for name in touched_names {
+                        let file_count = count_files(&watch_directory.join(&name));
+                        if counts.get(&name) != Some(&file_count) {
+                            counts.insert(name.clone(), file_count);
+                            statuses.push(DatasetStatus { name, file_count });
+                        }
+                    }
+                    drop(counts);
+                    emit_dataset_status(&app_handle, statuses);
+                }
+                Err(errors) => {
+                    for error in errors {
+                        log::warn!("watch error {error:?}");
+                    }
+                }
+            },
+        ) {
+            Ok(mut debouncer) => {
+                if let Err(error) = debouncer
+                    .watcher()
+                    .watch(&directory, notify::RecursiveMode::Recursive)
+                {
+                    log::warn!("watch error {error:?}");
+                }
+                self.datasets_watcher = Some(debouncer);
+            }
+            Err(error) => log::warn!("creating a dataset watcher failed with error {error:?}"),
+        }
+    }
+
+    fn unwatch_datasets(&mut self) {
+        self.datasets_watcher.take();
+        self.dataset_file_counts.lock().unwrap().clear();
+    }
 }
 
 enum Action {
     Context {
-        running: std::sync::Arc<AtomicBool>,
+        running: undr::RunControl,
         handle: tauri::async_runtime::JoinHandle<()>,
     },
     Cancelling,
@@ -62,6 +169,83 @@ struct SharedState {
     action: std::sync::Mutex<Action>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+struct LogEvent {
+    level: String,
+    target: String,
+    message: String,
+    timestamp_ms: u64,
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Opens the log file for appending, first moving it aside to `LOG_ROTATED_FILE_NAME` if it has
+/// already grown past `LOG_ROTATE_MAX_BYTES` (replacing whatever was rotated out last time).
+fn open_log_file(config_directory: &std::path::Path) -> std::io::Result<std::fs::File> {
+    let path = config_directory.join(LOG_FILE_NAME);
+    if let Ok(metadata) = std::fs::metadata(&path) {
+        if metadata.len() > LOG_ROTATE_MAX_BYTES {
+            std::fs::rename(&path, config_directory.join(LOG_ROTATED_FILE_NAME))?;
+        }
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+}
+
+/// Forwards every record accepted by `log::max_level()` to the frontend as a `log` event and
+/// appends it to the rotating log file in `app_config_dir()`, so errors survive in the packaged
+/// `windows_subsystem = "windows"` build where stderr is not visible to the user.
+struct FrontendLogger {
+    app_handle: tauri::AppHandle,
+    file: std::sync::Mutex<std::fs::File>,
+}
+
+impl log::Log for FrontendLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let log_event = LogEvent {
+            level: record.level().to_string(),
+            target: record.target().to_owned(),
+            message: record.args().to_string(),
+            timestamp_ms: now_ms(),
+        };
+        {
+            let mut file = self.file.lock().unwrap();
+            let line = format!(
+                "{} {} {} {}\n",
+                log_event.timestamp_ms, log_event.level, log_event.target, log_event.message
+            );
+            // a failure here cannot be routed back through `log::error!` without recursing into
+            // this same `log` call, so stderr is the only fallback left
+            if let Err(error) = file.write_all(line.as_bytes()) {
+                eprintln!("writing to the log file failed with error {error:?}");
+            }
+        }
+        if let Err(emit_error) = self.app_handle.emit_to("main", "log", log_event) {
+            eprintln!("emit_to('main', 'log', payload) failed with error {emit_error:?}");
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 enum CreateConfigurationError {
     Create(String),
@@ -122,12 +306,27 @@ impl ConfigurationPayload {
     }
 }
 
+/// Parses `path` and returns both the payload meant for the `configuration` event and, if
+/// parsing succeeded, the resolved `Configuration` so the caller can (re)start the datasets
+/// watch without parsing the file a second time.
+fn reload_configuration(path: std::path::PathBuf) -> (ConfigurationPayload, Option<undr::Configuration>) {
+    let result = undr::Configuration::from_path(&path);
+    let configuration = result.as_ref().ok().map(|(configuration, _)| configuration.clone());
+    (
+        ConfigurationPayload {
+            path,
+            configuration_or_error: result.into(),
+        },
+        configuration,
+    )
+}
+
 fn emit_configuration(
     app_handle: &tauri::AppHandle,
     configuration_payload: Option<ConfigurationPayload>,
 ) {
     if let Err(emit_error) = app_handle.emit_to("main", "configuration", configuration_payload) {
-        eprintln!("emit_to('main', 'configuration', payload) failed with error {emit_error:?}");
+        log::warn!("emit_to('main', 'configuration', payload) failed with error {emit_error:?}");
     }
 }
 
@@ -141,6 +340,18 @@ enum ActionType {
 
     #[serde(rename = "install")]
     Install,
+
+    #[serde(rename = "uninstall")]
+    Uninstall,
+
+    #[serde(rename = "plan")]
+    Plan,
+
+    #[serde(rename = "verify")]
+    Verify,
+
+    #[serde(rename = "repair")]
+    Repair,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -152,16 +363,28 @@ enum ActionPayload {
     #[serde(rename = "message")]
     Message(undr::Message),
 
+    #[serde(rename = "plan")]
+    Plan(undr::InstallPlan),
+
+    #[serde(rename = "verify")]
+    Verify(Vec<undr::VerifyMismatch>),
+
     #[serde(rename = "error")]
     Error(String),
 
     #[serde(rename = "end")]
     End,
+
+    #[serde(rename = "paused")]
+    Paused,
+
+    #[serde(rename = "resumed")]
+    Resumed,
 }
 
 fn emit_action(app_handle: &tauri::AppHandle, action_payload: ActionPayload) {
     if let Err(emit_error) = app_handle.emit_to("main", "action", action_payload) {
-        eprintln!("emit_to('main', 'action', payload) failed with error {emit_error:?}");
+        log::warn!("emit_to('main', 'action', payload) failed with error {emit_error:?}");
     }
 }
 
@@ -175,9 +398,15 @@ fn load_configuration(app_handle: tauri::AppHandle, path: Option<std::path::Path
         .unwrap();
     state.unwatch();
     if let Some(path) = path {
-        emit_configuration(&app_handle, Some(ConfigurationPayload::from_path(&path)));
+        let (configuration_payload, configuration) = reload_configuration(path.clone());
+        match &configuration {
+            Some(configuration) => state.watch_datasets(app_handle.clone(), configuration),
+            None => state.unwatch_datasets(),
+        }
+        emit_configuration(&app_handle, Some(configuration_payload));
         state.watch(path);
     } else {
+        state.unwatch_datasets();
         emit_configuration(&app_handle, None);
     }
 }
@@ -209,7 +438,12 @@ fn save_configuration(
             .map_err(|error| SaveConfigurationError::Seriliaze(format!("{error:?}")))?,
     )
     .map_err(|error| SaveConfigurationError::Write(format!("{error:?}")))?;
-    emit_configuration(&app_handle, Some(ConfigurationPayload::from_path(&path)));
+    let (configuration_payload, configuration) = reload_configuration(path.clone());
+    match &configuration {
+        Some(configuration) => state.watch_datasets(app_handle.clone(), configuration),
+        None => state.unwatch_datasets(),
+    }
+    emit_configuration(&app_handle, Some(configuration_payload));
     state.watch(path);
     Ok(())
 }
@@ -219,6 +453,34 @@ fn show_main_window(window: tauri::Window) {
     window.get_window("main").unwrap().show().unwrap();
 }
 
+#[tauri::command]
+fn get_log_path(app_handle: tauri::AppHandle) -> Result<std::path::PathBuf, PreferencesError> {
+    Ok(app_handle
+        .path_resolver()
+        .app_config_dir()
+        .ok_or_else(|| {
+            PreferencesError::Directory(
+                "tauri::api::path::app_config_dir returned None".to_owned(),
+            )
+        })?
+        .join(LOG_FILE_NAME))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+enum LogLevelError {
+    Unknown(String),
+}
+
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), LogLevelError> {
+    log::set_max_level(
+        level
+            .parse::<log::LevelFilter>()
+            .map_err(|_| LogLevelError::Unknown(level))?,
+    );
+    Ok(())
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 enum PreferencesError {
     Directory(String),
@@ -359,7 +621,7 @@ fn calc_size(
     match &*action {
         Action::None => {
             let app_handle = app_handle.clone();
-            let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+            let running = undr::RunControl::new();
             *action = Action::Context {
                 running: running.clone(),
                 handle: tauri::async_runtime::spawn(async move {
@@ -373,7 +635,11 @@ fn calc_size(
                     }
                     match configuration
                         .install(
-                            running,
+                            undr::InstallControl::new(
+                                running,
+                                undr::DownloadPermits(1),
+                                undr::DecodePermits(1),
+                            ),
                             |message| {
                                 emit_action(&app_handle, ActionPayload::Message(message));
                             },
@@ -381,10 +647,11 @@ fn calc_size(
                             undr::Keep(false),
                             undr::DispatchDois(false),
                             undr::CalculateSize(true),
+                            undr::Verify(false),
+                            undr::ContinueOnError(false),
                             undr::FilePermits(file_permits),
                             undr::DownloadIndexPermits(download_index_permits),
-                            undr::DownloadPermits(1),
-                            undr::DecodePermits(1),
+                            None,
                         )
                         .await
                     {
@@ -413,7 +680,8 @@ fn calc_size(
             Ok(())
         }
         _ => Err(ActionError::Active(
-            "there is already an active action (calc. size, cite, or install)".to_owned(),
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
         )),
     }
 }
@@ -429,7 +697,17 @@ fn cite(
     download_doi_permits: usize,
     doi_timeout: f64,
     output_path: std::path::PathBuf,
+    format: Option<String>,
 ) -> Result<(), ActionError> {
+    // lets the frontend force a format even when `output_path`'s extension does not hint at one
+    // (or the user wants a different format than the extension implies), instead of always
+    // deferring to `bibtex`'s own extension-based detection
+    let format = match format.as_deref() {
+        Some("bibtex") => Some(undr::CitationFormat::BibTex),
+        Some("csl-json") => Some(undr::CitationFormat::CslJson),
+        Some("ris") => Some(undr::CitationFormat::Ris),
+        _ => None,
+    };
     let mut action = app_handle
         .state::<SharedState>()
         .inner()
@@ -439,7 +717,7 @@ fn cite(
     match &*action {
         Action::None => {
             let app_handle = app_handle.clone();
-            let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+            let running = undr::RunControl::new();
             *action = Action::Context {
                 running: running.clone(),
                 handle: tauri::async_runtime::spawn(async move {
@@ -456,7 +734,10 @@ fn cite(
                             undr::DownloadDoiPermits(download_doi_permits),
                             Some(doi_timeout),
                             output_path,
+                            format,
                             undr::Pretty(true),
+                            undr::ContinueOnError(false),
+                            None,
                         )
                         .await
                     {
@@ -485,7 +766,8 @@ fn cite(
             Ok(())
         }
         _ => Err(ActionError::Active(
-            "there is already an active action (calc. size, cite, or install)".to_owned(),
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
         )),
     }
 }
@@ -497,6 +779,8 @@ fn install(
     configuration: undr::Configuration,
     force: bool,
     keep: bool,
+    verify: bool,
+    continue_on_error: bool,
     file_permits: usize,
     download_index_permits: usize,
     download_permits: usize,
@@ -511,14 +795,18 @@ fn install(
     match &*action {
         Action::None => {
             let app_handle = app_handle.clone();
-            let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+            let running = undr::RunControl::new();
             *action = Action::Context {
                 running: running.clone(),
                 handle: tauri::async_runtime::spawn(async move {
                     emit_action(&app_handle, ActionPayload::Start(ActionType::Install));
                     match configuration
                         .install(
-                            running,
+                            undr::InstallControl::new(
+                                running,
+                                undr::DownloadPermits(download_permits),
+                                undr::DecodePermits(decode_permits),
+                            ),
                             |message| {
                                 emit_action(&app_handle, ActionPayload::Message(message));
                             },
@@ -526,10 +814,276 @@ fn install(
                             undr::Keep(keep),
                             undr::DispatchDois(false),
                             undr::CalculateSize(false),
+                            undr::Verify(verify),
+                            undr::ContinueOnError(continue_on_error),
+                            undr::FilePermits(file_permits),
+                            undr::DownloadIndexPermits(download_index_permits),
+                            None,
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            emit_action(&app_handle, ActionPayload::End);
+                        }
+                        Err(error) => {
+                            emit_action(&app_handle, ActionPayload::Error(format!("{error:?}")));
+                        }
+                    }
+                    let mut action = app_handle
+                        .state::<SharedState>()
+                        .inner()
+                        .action
+                        .lock()
+                        .unwrap();
+                    if let Action::Context {
+                        running: _,
+                        handle: _,
+                    } = &*action
+                    {
+                        *action = Action::None;
+                    }
+                }),
+            };
+            Ok(())
+        }
+        _ => Err(ActionError::Active(
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
+        )),
+    }
+}
+
+#[tauri::command]
+fn uninstall(
+    app_handle: tauri::AppHandle,
+    configuration: undr::Configuration,
+    file_permits: usize,
+) -> Result<(), ActionError> {
+    let mut action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::None => {
+            let app_handle = app_handle.clone();
+            let running = undr::RunControl::new();
+            *action = Action::Context {
+                running: running.clone(),
+                handle: tauri::async_runtime::spawn(async move {
+                    emit_action(&app_handle, ActionPayload::Start(ActionType::Uninstall));
+                    match configuration
+                        .uninstall(
+                            running,
+                            |message| {
+                                emit_action(&app_handle, ActionPayload::Message(message));
+                            },
+                            undr::FilePermits(file_permits),
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            emit_action(&app_handle, ActionPayload::End);
+                        }
+                        Err(error) => {
+                            emit_action(&app_handle, ActionPayload::Error(format!("{error:?}")));
+                        }
+                    }
+                    let mut action = app_handle
+                        .state::<SharedState>()
+                        .inner()
+                        .action
+                        .lock()
+                        .unwrap();
+                    if let Action::Context {
+                        running: _,
+                        handle: _,
+                    } = &*action
+                    {
+                        *action = Action::None;
+                    }
+                }),
+            };
+            Ok(())
+        }
+        _ => Err(ActionError::Active(
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
+        )),
+    }
+}
+
+#[tauri::command]
+fn plan(
+    app_handle: tauri::AppHandle,
+    configuration: undr::Configuration,
+    force: bool,
+    file_permits: usize,
+    download_index_permits: usize,
+) -> Result<(), ActionError> {
+    let mut action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::None => {
+            let app_handle = app_handle.clone();
+            let running = undr::RunControl::new();
+            *action = Action::Context {
+                running: running.clone(),
+                handle: tauri::async_runtime::spawn(async move {
+                    emit_action(&app_handle, ActionPayload::Start(ActionType::Plan));
+                    match configuration
+                        .plan(
+                            running,
+                            |message| {
+                                emit_action(&app_handle, ActionPayload::Message(message));
+                            },
+                            undr::Force(force),
                             undr::FilePermits(file_permits),
                             undr::DownloadIndexPermits(download_index_permits),
-                            undr::DownloadPermits(download_permits),
-                            undr::DecodePermits(decode_permits),
+                            None,
+                        )
+                        .await
+                    {
+                        Ok(plan) => {
+                            emit_action(&app_handle, ActionPayload::Plan(plan));
+                            emit_action(&app_handle, ActionPayload::End);
+                        }
+                        Err(error) => {
+                            emit_action(&app_handle, ActionPayload::Error(format!("{error:?}")));
+                        }
+                    }
+                    let mut action = app_handle
+                        .state::<SharedState>()
+                        .inner()
+                        .action
+                        .lock()
+                        .unwrap();
+                    if let Action::Context {
+                        running: _,
+                        handle: _,
+                    } = &*action
+                    {
+                        *action = Action::None;
+                    }
+                }),
+            };
+            Ok(())
+        }
+        _ => Err(ActionError::Active(
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
+        )),
+    }
+}
+
+#[tauri::command]
+fn verify(
+    app_handle: tauri::AppHandle,
+    configuration: undr::Configuration,
+    file_permits: usize,
+) -> Result<(), ActionError> {
+    let mut action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::None => {
+            let app_handle = app_handle.clone();
+            let running = undr::RunControl::new();
+            *action = Action::Context {
+                running: running.clone(),
+                handle: tauri::async_runtime::spawn(async move {
+                    emit_action(&app_handle, ActionPayload::Start(ActionType::Verify));
+                    match configuration
+                        .verify(
+                            running,
+                            |message| {
+                                emit_action(&app_handle, ActionPayload::Message(message));
+                            },
+                            undr::FilePermits(file_permits),
+                        )
+                        .await
+                    {
+                        Ok(mismatches) => {
+                            emit_action(&app_handle, ActionPayload::Verify(mismatches));
+                            emit_action(&app_handle, ActionPayload::End);
+                        }
+                        Err(error) => {
+                            emit_action(&app_handle, ActionPayload::Error(format!("{error:?}")));
+                        }
+                    }
+                    let mut action = app_handle
+                        .state::<SharedState>()
+                        .inner()
+                        .action
+                        .lock()
+                        .unwrap();
+                    if let Action::Context {
+                        running: _,
+                        handle: _,
+                    } = &*action
+                    {
+                        *action = Action::None;
+                    }
+                }),
+            };
+            Ok(())
+        }
+        _ => Err(ActionError::Active(
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
+        )),
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn repair(
+    app_handle: tauri::AppHandle,
+    configuration: undr::Configuration,
+    mismatches: Vec<String>,
+    keep: bool,
+    file_permits: usize,
+    download_index_permits: usize,
+    download_permits: usize,
+    decode_permits: usize,
+) -> Result<(), ActionError> {
+    let mut action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::None => {
+            let app_handle = app_handle.clone();
+            let running = undr::RunControl::new();
+            *action = Action::Context {
+                running: running.clone(),
+                handle: tauri::async_runtime::spawn(async move {
+                    emit_action(&app_handle, ActionPayload::Start(ActionType::Repair));
+                    match configuration
+                        .repair(
+                            undr::InstallControl::new(
+                                running,
+                                undr::DownloadPermits(download_permits),
+                                undr::DecodePermits(decode_permits),
+                            ),
+                            |message| {
+                                emit_action(&app_handle, ActionPayload::Message(message));
+                            },
+                            mismatches,
+                            undr::Keep(keep),
+                            undr::FilePermits(file_permits),
+                            undr::DownloadIndexPermits(download_index_permits),
+                            None,
                         )
                         .await
                     {
@@ -558,7 +1112,8 @@ fn install(
             Ok(())
         }
         _ => Err(ActionError::Active(
-            "there is already an active action (calc. size, cite, or install)".to_owned(),
+            "there is already an active action (calc. size, cite, install, uninstall, plan, verify, or repair)"
+                .to_owned(),
         )),
     }
 }
@@ -575,7 +1130,7 @@ fn cancel(app_handle: tauri::AppHandle) -> Result<(), ActionError> {
     match action {
         Action::Context { running, handle } => {
             tauri::async_runtime::block_on(async move {
-                running.store(false, std::sync::atomic::Ordering::Release);
+                running.stop();
                 handle.abort();
                 _ = handle.await;
                 *app_handle
@@ -591,6 +1146,47 @@ fn cancel(app_handle: tauri::AppHandle) -> Result<(), ActionError> {
     }
 }
 
+/// Suspends the active action at its next file boundary without discarding progress already
+/// made, unlike `cancel` which tears the task down. The frontend is told about the transition
+/// via an `ActionPayload` so it can flip its pause/resume button accordingly.
+#[tauri::command]
+fn pause(app_handle: tauri::AppHandle) -> Result<(), ActionError> {
+    let action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::Context { running, handle: _ } => {
+            running.pause();
+            drop(action);
+            emit_action(&app_handle, ActionPayload::Paused);
+            Ok(())
+        }
+        _ => Err(ActionError::Active("there is no active action".to_owned())),
+    }
+}
+
+#[tauri::command]
+fn resume(app_handle: tauri::AppHandle) -> Result<(), ActionError> {
+    let action = app_handle
+        .state::<SharedState>()
+        .inner()
+        .action
+        .lock()
+        .unwrap();
+    match &*action {
+        Action::Context { running, handle: _ } => {
+            running.resume();
+            drop(action);
+            emit_action(&app_handle, ActionPayload::Resumed);
+            Ok(())
+        }
+        _ => Err(ActionError::Active("there is no active action".to_owned())),
+    }
+}
+
 fn main() {
     tauri::Builder::default()
         .setup(|app| {
@@ -621,6 +1217,19 @@ fn main() {
                 });
             }
             let app_handle = app.handle();
+            match open_log_file(&config_directory) {
+                Ok(file) => {
+                    let logger = Box::leak(Box::new(FrontendLogger {
+                        app_handle: app_handle.clone(),
+                        file: std::sync::Mutex::new(file),
+                    }));
+                    if let Err(error) = log::set_logger(logger) {
+                        eprintln!("log::set_logger failed with error {error:?}");
+                    }
+                    log::set_max_level(log::LevelFilter::Info);
+                }
+                Err(error) => eprintln!("opening the log file failed with error {error:?}"),
+            }
             app.manage(SharedState {
                 state: std::sync::Mutex::new(State {
                     watcher: notify::recommended_watcher(
@@ -631,25 +1240,41 @@ fn main() {
                                 | notify::EventKind::Create(_)
                                 | notify::EventKind::Modify(_) => {
                                     if event.paths.len() == 1 {
-                                        emit_configuration(
-                                            &app_handle,
-                                            Some(ConfigurationPayload::from_path(&event.paths[0])),
-                                        );
+                                        let (configuration_payload, configuration) =
+                                            reload_configuration(event.paths[0].clone());
+                                        {
+                                            let mut state = app_handle
+                                                .state::<SharedState>()
+                                                .inner()
+                                                .state
+                                                .lock()
+                                                .unwrap();
+                                            match &configuration {
+                                                Some(configuration) => state
+                                                    .watch_datasets(app_handle.clone(), configuration),
+                                                None => state.unwatch_datasets(),
+                                            }
+                                        }
+                                        emit_configuration(&app_handle, Some(configuration_payload));
                                     }
                                 }
                                 notify::EventKind::Remove(_) | notify::EventKind::Other => {}
                             },
-                            Err(error) => eprintln!("watch error {error:?}"),
+                            Err(error) => log::warn!("watch error {error:?}"),
                         },
                     )
                     .map_or_else(
                         |error| {
-                            eprintln!("creating a watched failed with error {error:?}");
+                            log::error!("creating a watcher failed with error {error:?}");
                             None
                         },
                         Some,
                     ),
                     path: None,
+                    datasets_watcher: None,
+                    dataset_file_counts: std::sync::Arc::new(std::sync::Mutex::new(
+                        std::collections::HashMap::new(),
+                    )),
                 }),
                 action: std::sync::Mutex::new(Action::None),
             });
@@ -662,11 +1287,19 @@ fn main() {
             show_main_window,
             load_preferences,
             store_preferences,
+            get_log_path,
+            set_log_level,
             reveal_in_os,
             calc_size,
             cite,
             install,
+            uninstall,
+            plan,
+            verify,
+            repair,
             cancel,
+            pause,
+            resume,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
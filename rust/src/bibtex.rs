@@ -1,57 +1,165 @@
 use crate::types;
 
+type DoiToPathIdsAndContent =
+    std::collections::HashMap<types::Doi, (Vec<types::PathId>, Option<String>)>;
+
+/// Serializes `doi_to_path_ids_and_content` to `path` in whichever `format` the caller resolved
+/// (see `types::CitationFormat`), carrying the same `% path_ids` / `% DOI` provenance the original
+/// BibTeX-only version of this function wrote, translated into each format's own notes
+/// convention.
 pub fn write<P: AsRef<std::path::Path>>(
     path: P,
-    doi_to_path_ids_and_content: &std::collections::HashMap<
-        types::Doi,
-        (Vec<types::PathId>, Option<String>),
-    >,
+    doi_to_path_ids_and_content: &DoiToPathIdsAndContent,
+    format: types::CitationFormat,
 ) -> std::io::Result<()> {
-    std::fs::write(&path, {
-        let mut dois_and_path_ids_and_content = doi_to_path_ids_and_content
+    std::fs::write(
+        &path,
+        match format {
+            types::CitationFormat::BibTex => write_bibtex(doi_to_path_ids_and_content),
+            types::CitationFormat::CslJson => write_csl_json(doi_to_path_ids_and_content),
+            types::CitationFormat::Ris => write_ris(doi_to_path_ids_and_content),
+        },
+    )
+}
+
+fn sorted_entries(
+    doi_to_path_ids_and_content: &DoiToPathIdsAndContent,
+) -> Vec<(&types::Doi, Vec<types::PathId>, &Option<String>)> {
+    let mut entries = doi_to_path_ids_and_content
+        .iter()
+        .map(|(doi, (ref path_ids, ref content))| {
+            let mut path_ids = path_ids.clone();
+            path_ids.sort_by(|a, b| a.0.cmp(&b.0));
+            (doi, path_ids, content)
+        })
+        .collect::<Vec<(&types::Doi, Vec<types::PathId>, &Option<String>)>>();
+    entries.sort_by(|a, b| a.1.first().unwrap().0.cmp(&b.1.first().unwrap().0));
+    entries
+}
+
+/// The `path_ids` list shown in each format's provenance note, abbreviated past 5 entries the
+/// same way regardless of format.
+fn path_ids_summary(path_ids: &[types::PathId]) -> String {
+    if path_ids.len() < 6 {
+        path_ids
             .iter()
-            .map(|(doi, (ref path_ids, ref content))| {
-                let mut path_ids = path_ids.clone();
-                path_ids.sort_by(|a, b| a.0.cmp(&b.0));
-                (doi, path_ids, content)
-            })
-            .collect::<Vec<(&types::Doi, Vec<types::PathId>, &Option<String>)>>();
-        dois_and_path_ids_and_content
-            .sort_by(|a, b| a.1.first().unwrap().0.cmp(&b.1.first().unwrap().0));
-        let mut combined = String::new();
-        for (doi, path_ids, content) in dois_and_path_ids_and_content {
-            if !combined.is_empty() {
-                combined.push('\n');
-            }
-            if path_ids.len() < 6 {
-                combined.push_str(&format!(
-                    "% {}\n",
-                    path_ids
-                        .iter()
-                        .map(|path_id| &*path_id.0)
-                        .collect::<Vec<&str>>()
-                        .join(", "),
-                ));
-            } else {
-                combined.push_str(&format!(
-                    "% {}, ... ({} more), {}\n",
-                    path_ids
-                        .iter()
-                        .take(3)
-                        .map(|path_id| &*path_id.0)
-                        .collect::<Vec<&str>>()
-                        .join(", "),
-                    path_ids.len() - 4,
-                    path_ids.last().unwrap().0,
-                ));
-            }
-            combined.push_str(&format!("% DOI {}\n", &doi.0));
-            if let Some(content) = content {
-                combined.push_str(content);
+            .map(|path_id| &*path_id.0)
+            .collect::<Vec<&str>>()
+            .join(", ")
+    } else {
+        format!(
+            "{}, ... ({} more), {}",
+            path_ids
+                .iter()
+                .take(3)
+                .map(|path_id| &*path_id.0)
+                .collect::<Vec<&str>>()
+                .join(", "),
+            path_ids.len() - 4,
+            path_ids.last().unwrap().0,
+        )
+    }
+}
+
+fn write_bibtex(doi_to_path_ids_and_content: &DoiToPathIdsAndContent) -> String {
+    let mut combined = String::new();
+    for (doi, path_ids, content) in sorted_entries(doi_to_path_ids_and_content) {
+        if !combined.is_empty() {
+            combined.push('\n');
+        }
+        combined.push_str(&format!("% {}\n", path_ids_summary(&path_ids)));
+        combined.push_str(&format!("% DOI {}\n", &doi.0));
+        if let Some(content) = content {
+            combined.push_str(content);
+        }
+    }
+    combined
+}
+
+/// CSL-JSON has no comment syntax, so the provenance that BibTeX prepends as `%` lines is instead
+/// merged into each entry's `note` field (the convention reference managers such as Zotero already
+/// use for free-text annotations), with any `note` doi.org's response already carried kept ahead
+/// of it. An entry whose content failed to download or did not parse as a CSL-JSON object is still
+/// emitted, as a stub object carrying only `id` and `note`, so the provenance is not silently
+/// dropped and the array stays valid JSON.
+fn write_csl_json(doi_to_path_ids_and_content: &DoiToPathIdsAndContent) -> String {
+    let entries = sorted_entries(doi_to_path_ids_and_content)
+        .into_iter()
+        .map(|(doi, path_ids, content)| {
+            let provenance = format!("{}\nDOI {}", path_ids_summary(&path_ids), &doi.0);
+            let mut object = content
+                .as_ref()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(content).ok())
+                .and_then(|value| match value {
+                    serde_json::Value::Object(object) => Some(object),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            object
+                .entry("id")
+                .or_insert_with(|| serde_json::Value::String(doi.0.clone()));
+            let note = match object.get("note").and_then(serde_json::Value::as_str) {
+                Some(note) if !note.is_empty() => format!("{note}\n\n{provenance}"),
+                _ => provenance,
+            };
+            object.insert("note".to_string(), serde_json::Value::String(note));
+            serde_json::Value::Object(object)
+        })
+        .collect::<Vec<serde_json::Value>>();
+    // `Pretty` only matters for BibTeX's brace-depth indentation; the structured JSON output is
+    // always emitted the same way regardless of it.
+    serde_json::to_string_pretty(&serde_json::Value::Array(entries))
+        .unwrap_or_default()
+}
+
+/// RIS has no comment syntax either, so the provenance is added as extra `N1` (notes) tag lines
+/// inserted right before the record's closing `ER` tag. An entry with no downloaded content (or
+/// content that does not look like an RIS record) falls back to a minimal generic-type record so
+/// the provenance still ends up in the file.
+fn write_ris(doi_to_path_ids_and_content: &DoiToPathIdsAndContent) -> String {
+    let mut combined = String::new();
+    for (doi, path_ids, content) in sorted_entries(doi_to_path_ids_and_content) {
+        if !combined.is_empty() {
+            combined.push('\n');
+        }
+        let notes = [
+            format!("N1  - {}", path_ids_summary(&path_ids)),
+            format!("N1  - DOI {}", &doi.0),
+        ];
+        let record = content
+            .as_ref()
+            .filter(|content| content.contains("ER  -") || content.contains("ER  - "));
+        combined.push_str(&match record {
+            Some(content) => insert_ris_notes(content, &notes),
+            None => {
+                let mut lines = vec!["TY  - GEN".to_string()];
+                lines.extend(notes);
+                if let Some(content) = content {
+                    lines.push(format!("N1  - {content}"));
+                }
+                lines.push("ER  - ".to_string());
+                lines.join("\n")
             }
+        });
+        if !combined.ends_with('\n') {
+            combined.push('\n');
         }
-        combined
-    })
+    }
+    combined
+}
+
+/// Inserts `notes` as `N1` lines directly above an RIS record's closing `ER` tag, leaving every
+/// other line untouched.
+fn insert_ris_notes(content: &str, notes: &[String]) -> String {
+    let mut lines = content.lines().map(str::to_string).collect::<Vec<String>>();
+    match lines
+        .iter()
+        .rposition(|line| line.trim_start().starts_with("ER"))
+    {
+        Some(index) => lines.splice(index..index, notes.iter().cloned()),
+        None => lines.extend(notes.iter().cloned()),
+    };
+    lines.join("\n")
 }
 
 pub fn prettify(bibtex: &String) -> String {
@@ -40,12 +40,21 @@ pub struct DatasetSettings {
     pub url: url::Url,
     pub mode: Mode,
     pub timeout: Option<f64>,
+    pub max_retries: Option<u32>,
+    pub max_bytes_per_second: Option<u64>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Configuration {
     pub directory: std::path::PathBuf,
     pub datasets: Vec<DatasetSettings>,
+
+    /// Set by `from_path` when `directory` parsed as an object-store URL (`s3://`, `gs://`,
+    /// `memory://`, `file://`); `None` means `directory` is a plain local path, resolved and
+    /// canonicalized the way it always has been. Not (de)serialized: it is derived from
+    /// `directory` every time the configuration is loaded, never configured directly.
+    #[serde(skip)]
+    pub directory_url: Option<url::Url>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -70,6 +79,9 @@ pub enum ConfigurationError {
 
     #[error("the timeout is negative")]
     NegativeTimeout(f64),
+
+    #[error("the bandwidth limit is zero")]
+    ZeroBandwidthLimit,
 }
 
 impl Configuration {
@@ -96,21 +108,47 @@ impl Configuration {
                         return Err(ConfigurationError::NegativeTimeout(timeout));
                     }
                 }
+                if dataset.max_bytes_per_second == Some(0) {
+                    return Err(ConfigurationError::ZeroBandwidthLimit);
+                }
             }
         }
         let datasets_directory = configuration.directory.clone();
-        if configuration.directory.is_relative() {
-            configuration.directory = path
-                .parent()
-                .ok_or(ConfigurationError::NoParent {
-                    path: path.clone(),
-                    directory: configuration.directory.clone(),
-                })?
-                .join(&configuration.directory)
+        if let Some(directory_str) = configuration.directory.to_str() {
+            if let Ok(url) = url::Url::parse(directory_str) {
+                if crate::storage::is_object_store_scheme(url.scheme()) {
+                    configuration.directory_url = Some(url);
+                }
+            }
+        }
+        if configuration.directory_url.is_none() {
+            if configuration.directory.is_relative() {
+                configuration.directory = path
+                    .parent()
+                    .ok_or(ConfigurationError::NoParent {
+                        path: path.clone(),
+                        directory: configuration.directory.clone(),
+                    })?
+                    .join(&configuration.directory)
+            }
+            // canonicalize only works with existing files / directories
+            // std::path::PathBuf::components performs fewer but useful normalizations and does not check the file system
+            configuration.directory = configuration.directory.components().collect();
         }
-        // canonicalize only works with existing files / directories
-        // std::path::PathBuf::components performs fewer but useful normalizations and does not check the file system
-        configuration.directory = configuration.directory.components().collect();
         Ok((configuration, datasets_directory))
     }
+
+    /// The backend `install`, `bibtex`, and `plan` land their files in: the local filesystem
+    /// unless `directory` parsed as an object-store URL in `from_path`, in which case the
+    /// matching `object_store` client.
+    pub fn storage(
+        &self,
+    ) -> Result<std::sync::Arc<dyn crate::storage::Storage>, crate::types::ActionError> {
+        match &self.directory_url {
+            Some(url) => Ok(std::sync::Arc::new(crate::storage::ObjectStorage::new(
+                url,
+            )?)),
+            None => Ok(std::sync::Arc::new(crate::storage::LocalStorage)),
+        }
+    }
 }
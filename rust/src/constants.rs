@@ -3,3 +3,24 @@ pub const PROGRESS_SIZE: i64 = 131072;
 pub const DECOMPRESS_SUFFIX: &str = ".decompress";
 pub const DEFAULT_TIMEOUT: f64 = 60.0;
 pub const DOWNLOAD_SUFFIX: &str = ".download";
+pub const BITFIELD_SUFFIX: &str = ".bitfield";
+pub const CHUNK_STORE_SUFFIX: &str = "-chunks";
+// name of the per-root completed-install ledger (see `ledger`), sharing `-index.json`'s leading
+// dash so it sorts ahead of dataset directories and is never mistaken for one
+pub const LEDGER_FILE_NAME: &str = "-undr-state.json";
+pub const CHUNK_MIN_SIZE: usize = 1 << 14; // 16 KiB
+pub const CHUNK_MAX_SIZE: usize = 1 << 20; // 1 MiB
+pub const CHUNK_AVERAGE_SIZE_LOG2: u32 = 16; // 64 KiB average chunk size
+// FastCDC-style normalized chunking: below the average size the cut mask gains this many extra
+// 1-bits (making a cut roughly `1 << CHUNK_NORMALIZATION_LEVEL` times less likely), and above the
+// average size it loses that many, so boundaries cluster near the target instead of spreading
+// across the whole [min_size, max_size] range.
+pub const CHUNK_NORMALIZATION_LEVEL: u32 = 1;
+pub const DEFAULT_MAX_RETRIES: u32 = 5;
+pub const RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+pub const RETRY_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+// how many seconds' worth of bytes a dataset's rate limiter lets a download burst through before
+// throttling kicks in, on top of the configured steady-state bytes/sec rate; each dataset's
+// `remote::Server` has its own limiter, so the limit is not shared across datasets
+pub const RATE_LIMIT_BURST_SECONDS: f64 = 2.0;
@@ -1,12 +1,104 @@
 use crate::constants;
 use crate::types;
-use sha3::Digest;
 use std::io::Read;
 use std::io::Write;
 
+/// A decompression codec that can be streamed over an async byte source, used by
+/// `remote::Server::download_file` to decode a compressed remote variant as its bytes arrive
+/// instead of staging the compressed bytes on disk and decoding them in a second pass, and by
+/// `decompress` below to decode an already-downloaded compressed variant from disk.
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    Brotli,
+    Zstd,
+    Xz,
+    Gzip,
+}
+
+impl Codec {
+    /// Picks the codec that matches a resource's `json_index::Compression` tag, the source of
+    /// truth used everywhere else in the install pipeline. `NoneCompression` has no codec.
+    pub fn from_compression(compression: &crate::json_index::Compression) -> Option<Codec> {
+        match compression {
+            crate::json_index::Compression::NoneCompression { .. } => None,
+            crate::json_index::Compression::Brotli { .. } => Some(Codec::Brotli),
+            crate::json_index::Compression::Zstd { .. } => Some(Codec::Zstd),
+            crate::json_index::Compression::Xz { .. } => Some(Codec::Xz),
+            crate::json_index::Compression::Gzip { .. } => Some(Codec::Gzip),
+        }
+    }
+
+    /// Falls back to sniffing the leading magic bytes of a compressed variant when no
+    /// `Compression` tag is available to consult (for instance a manifest written by a tool that
+    /// predates the `type` field). Brotli has no magic number, so it cannot be recognised this
+    /// way; `None` covers that case along with a truncated or genuinely unrecognised header.
+    pub fn sniff(header: &[u8]) -> Option<Codec> {
+        if header.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(Codec::Zstd)
+        } else if header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z']) {
+            Some(Codec::Xz)
+        } else if header.starts_with(&[0x1F, 0x8B]) {
+            Some(Codec::Gzip)
+        } else {
+            None
+        }
+    }
+
+    pub fn wrap(
+        self,
+        stream: std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>,
+    ) -> std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>> {
+        let buffered = tokio::io::BufReader::new(stream);
+        match self {
+            Codec::Brotli => Box::pin(async_compression::tokio::bufread::BrotliDecoder::new(
+                buffered,
+            )),
+            Codec::Zstd => Box::pin(async_compression::tokio::bufread::ZstdDecoder::new(
+                buffered,
+            )),
+            Codec::Xz => Box::pin(async_compression::tokio::bufread::XzDecoder::new(buffered)),
+            Codec::Gzip => Box::pin(async_compression::tokio::bufread::GzipDecoder::new(
+                buffered,
+            )),
+        }
+    }
+
+    pub(crate) fn reader<R: Read + Send + 'static>(
+        self,
+        file: R,
+    ) -> Result<Box<dyn Read + Send>, types::DecompressError> {
+        Ok(match self {
+            Codec::Brotli => Box::new(brotli::Decompressor::new(
+                file,
+                constants::DECOMPRESS_CHUNK_SIZE,
+            )),
+            Codec::Zstd => Box::new(zstd::Decoder::new(file)?),
+            Codec::Xz => Box::new(xz2::read::XzDecoder::new(file)),
+            Codec::Gzip => Box::new(flate2::read::GzDecoder::new(file)),
+        })
+    }
+}
+
+// Random access into a compressed variant (a `read_range(path_id, start, len)` seeking past a
+// checkpoint sidecar instead of decoding from byte 0) was attempted and reverted: none of
+// `brotli`/`zstd`/`xz2`/`flate2`'s public `Read` APIs expose a serializable mid-stream resume
+// point, so a recorded compressed-byte offset is not a valid place to restart any of them from —
+// it can only land inside a block/frame the decoder has no way to resynchronize to. A correct
+// version would need each codec's internal dictionary/bit-buffer state preserved and restored,
+// which these crates do not support. `mount::Filesystem::read_at` gets sound random access
+// instead by keeping a parked decoder per file and discarding forward to the requested offset,
+// restarting from the start only for a read behind the decoder that its output cache misses,
+// trading away the performance win a checkpoint index would have given; this request is closed
+// as won't-fix rather than shipped broken.
+
+/// Decompresses an already-downloaded compressed variant of a resource using whichever `Codec`
+/// the caller resolved (typically via `Codec::from_compression`, falling back to `Codec::sniff`),
+/// verifying the decompressed size and hash against the resource's index entry and renaming the
+/// result into place on success — the same pipeline regardless of which codec produced it.
 #[allow(clippy::too_many_arguments)]
-pub fn brotli<Message>(
-    running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+pub fn decompress<Message>(
+    codec: Codec,
+    running: types::RunControl,
     sender: &tokio::sync::mpsc::UnboundedSender<Message>,
     path_root: types::PathRoot,
     path_id: &types::PathId,
@@ -20,24 +112,22 @@ where
     Message: std::convert::From<types::DecodeProgress>,
     Message: std::fmt::Debug,
 {
+    let storage = path_root.storage();
     let file_path = path_root.join(path_id);
-    if !force.0 {
-        match std::fs::metadata(&file_path) {
-            Ok(metadata) if metadata.file_type().is_file() => return Ok(()),
-            _ => (),
-        }
+    if !force.0 && storage.is_file(&file_path) {
+        return Ok(());
     }
+    // the compressed variant is always staged on local disk by the download step regardless of
+    // where `storage` lands the decompressed output, so it is read directly rather than through
+    // the trait
     let compressed_path = path_root.join_with_suffix(path_id, &suffix.0);
     let decompress_path =
         path_root.join_with_suffixes(path_id, &suffix.0, constants::DECOMPRESS_SUFFIX);
-    let mut hasher = sha3::Sha3_224::new();
+    let mut hasher = expected_hash.algorithm.hasher();
     let mut size = 0;
     {
-        let mut reader = brotli::Decompressor::new(
-            std::fs::File::open(&compressed_path)?,
-            constants::DECOMPRESS_CHUNK_SIZE,
-        );
-        let mut writer = std::fs::File::create(&decompress_path)?;
+        let mut reader = codec.reader(std::fs::File::open(&compressed_path)?)?;
+        let mut writer = storage.create(&decompress_path)?;
         let mut buffer = [0u8; constants::DECOMPRESS_CHUNK_SIZE];
         let mut progress_size = 0;
         loop {
@@ -46,7 +136,7 @@ where
                     if chunk_size == 0 {
                         break;
                     }
-                    if !running.load(std::sync::atomic::Ordering::Acquire) {
+                    if running.is_stopped() {
                         return Err(types::DecompressError::Interrupted);
                     }
                     writer.write_all(&buffer[0..chunk_size])?;
@@ -94,12 +184,15 @@ where
                 .map_err(|_| types::DecompressError::Send(path_id.clone()))?;
         }
     }
-    let hash = hasher.finalize();
-    if hash != expected_hash.0 {
+    let digest = hasher.finalize();
+    if digest != expected_hash.digest {
         return Err(types::DecompressError::Hash {
             path_id: path_id.clone(),
             expected: expected_hash.clone(),
-            downloaded: types::Hash(hash),
+            downloaded: types::Hash {
+                algorithm: expected_hash.algorithm,
+                digest,
+            },
         });
     }
     if size != expected_size {
@@ -109,9 +202,9 @@ where
             downloaded: size,
         });
     }
-    std::fs::rename(&decompress_path, &file_path)?;
+    storage.rename(&decompress_path, &file_path)?;
     if !keep.0 {
-        let _ = std::fs::remove_file(compressed_path);
+        let _ = storage.remove_file(&compressed_path);
     }
     sender
         .send(
@@ -2,13 +2,137 @@ use crate::configuration;
 use crate::constants;
 use crate::decode;
 use crate::json_index;
+use crate::ledger;
 use crate::remote;
+use crate::store;
 use crate::types;
 use futures::future::FutureExt;
 
+/// Recursively deletes the files an installed dataset directory's index says belong to it, plus
+/// the index itself, removing directories once they are empty. Filesystem failures (permission
+/// denied, a file in use, a file that was already missing, ...) are collected into the returned
+/// list instead of aborting the walk, so a single stuck file does not prevent the rest of the
+/// dataset from being uninstalled.
+pub fn uninstall_directory(
+    running: types::RunControl,
+    sender: tokio::sync::mpsc::UnboundedSender<types::Message>,
+    path_root: types::PathRoot,
+    path_id: types::PathId,
+    file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+) -> std::pin::Pin<
+    std::boxed::Box<
+        dyn futures::future::Future<
+                Output = Result<Vec<types::UninstallFailure>, types::ActionError>,
+            > + Send,
+    >,
+> {
+    async move {
+        let mut failures = Vec::new();
+        let index_path_id = path_id.join(&types::Name("-index.json".to_owned()));
+        let index = {
+            let _permit = file_semaphore.acquire().await?;
+            match std::fs::read(path_root.join(&index_path_id)) {
+                Ok(content) => Some(json_index::Index::from_bytes(&content)?),
+                Err(_) => None,
+            }
+        };
+        if let Some(index) = index {
+            let mut join_set = tokio::task::JoinSet::new();
+            for directory in &index.directories {
+                let running = running.clone();
+                let sender = sender.clone();
+                let path_root = path_root.clone();
+                let path_id = path_id.join(directory);
+                let file_semaphore = file_semaphore.clone();
+                join_set.spawn(uninstall_directory(
+                    running,
+                    sender,
+                    path_root,
+                    path_id,
+                    file_semaphore,
+                ));
+            }
+            while let Some(task) = join_set.join_next().await {
+                match task {
+                    Ok(result) => failures.extend(result?),
+                    Err(error) => return Err(types::ActionError::Join(error)),
+                }
+            }
+            for resource in index.files.iter().map(|file| &file.resource).chain(
+                index
+                    .other_files
+                    .iter()
+                    .map(|other_file| &other_file.resource),
+            ) {
+                if running.is_stopped() {
+                    return Ok(failures);
+                }
+                running.wait_if_paused().await;
+                let resource_path_id = path_id.join(&resource.name);
+                let resource_path = path_root.join(&resource_path_id);
+                let _permit = file_semaphore.acquire().await?;
+                match std::fs::remove_file(&resource_path) {
+                    Ok(()) => {
+                        sender
+                            .send(types::Message::UninstallProgress {
+                                path_id: resource_path_id,
+                                error: None,
+                            })
+                            .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                    }
+                    Err(error) => {
+                        let error = format!("{error:?}");
+                        sender
+                            .send(types::Message::UninstallProgress {
+                                path_id: resource_path_id.clone(),
+                                error: Some(error.clone()),
+                            })
+                            .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                        failures.push(types::UninstallFailure {
+                            path_id: resource_path_id,
+                            error,
+                        });
+                    }
+                }
+            }
+            let _permit = file_semaphore.acquire().await?;
+            match std::fs::remove_file(path_root.join(&index_path_id)) {
+                Ok(()) => {
+                    sender
+                        .send(types::Message::UninstallProgress {
+                            path_id: index_path_id,
+                            error: None,
+                        })
+                        .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                }
+                Err(error) => {
+                    let error = format!("{error:?}");
+                    sender
+                        .send(types::Message::UninstallProgress {
+                            path_id: index_path_id.clone(),
+                            error: Some(error.clone()),
+                        })
+                        .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                    failures.push(types::UninstallFailure {
+                        path_id: index_path_id,
+                        error,
+                    });
+                }
+            }
+        }
+        // `remove_dir` only succeeds when the directory is empty, which is exactly what should
+        // gate it here: a directory that still holds files not listed in the index (or left
+        // behind by a failed removal above) is simply left in place rather than reported as a
+        // failure.
+        let _ = std::fs::remove_dir(path_root.join(&path_id));
+        Ok(failures)
+    }
+    .boxed()
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn install_directory(
-    running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    running: types::RunControl,
     server: remote::Server,
     sender: tokio::sync::mpsc::UnboundedSender<types::Message>,
     path_root: types::PathRoot,
@@ -17,11 +141,13 @@ pub fn install_directory(
     keep: types::Keep,
     dispatch_dois: types::DispatchDois,
     calculate_size: types::CalculateSize,
+    verify: types::Verify,
     mode: configuration::InstallableMode,
     file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
-    download_index_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
-    download_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
-    decode_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+    download_index_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
+    download_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
+    decode_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
+    ledger: ledger::SharedLedger,
 ) -> std::pin::Pin<
     std::boxed::Box<dyn futures::future::Future<Output = Result<(), types::ActionError>> + Send>,
 > {
@@ -67,18 +193,28 @@ pub fn install_directory(
                 None,
                 None,
                 &types::Name(String::new()),
+                None,
                 download_index_semaphore.clone(),
                 file_semaphore.clone(),
             )
             .await?;
-        let index: json_index::Index = {
-            let content = {
-                let _permit = file_semaphore.acquire().await?;
-                std::fs::read_to_string(path_root.join(&index_path_id))
-                    .map_err(|_| types::ActionError::Read(path_root.join(&index_path_id)))?
-            };
-            serde_json::from_str(&content)?
+        let content = {
+            let _permit = file_semaphore.acquire().await?;
+            std::fs::read(path_root.join(&index_path_id))
+                .map_err(|_| types::ActionError::Read(path_root.join(&index_path_id)))?
         };
+        // hashed independently of the index's own content (there is no content hash in the
+        // `-index.json` schema itself) purely to give the ledger something to invalidate a
+        // resource's entry against whenever this directory's index is republished or edited.
+        let index_hash = {
+            let mut hasher = types::Algorithm::Sha3_224.hasher();
+            hasher.update(&content);
+            types::Hash {
+                algorithm: types::Algorithm::Sha3_224,
+                digest: hasher.finalize(),
+            }
+        };
+        let index: json_index::Index = json_index::Index::from_bytes(&content)?;
         sender
             .send(types::Message::IndexLoaded {
                 path_id: path_id.clone(),
@@ -106,6 +242,7 @@ pub fn install_directory(
             let download_index_semaphore = download_index_semaphore.clone();
             let download_semaphore = download_semaphore.clone();
             let decode_semaphore = decode_semaphore.clone();
+            let ledger = ledger.clone();
             join_set.spawn(async move {
                 install_directory(
                     running,
@@ -117,11 +254,13 @@ pub fn install_directory(
                     keep,
                     dispatch_dois,
                     calculate_size,
+                    verify,
                     mode,
                     file_semaphore,
                     download_index_semaphore,
                     download_semaphore,
                     decode_semaphore,
+                    ledger,
                 )
                 .await?;
                 Ok::<(), types::ActionError>(())
@@ -258,10 +397,49 @@ pub fn install_directory(
                         .iter()
                         .map(|other_file| &other_file.resource),
                 ) {
+                    if running.is_stopped() {
+                        break;
+                    }
+                    running.wait_if_paused().await;
+                    let resource_path_id = path_id.join(&resource.name);
+                    if !force.0 {
+                        // a ledger hit only means this resource was fully verified on an earlier
+                        // run and its parent index hasn't changed since; it does not mean the
+                        // file is still there, so the final on-disk path is always `stat`ed and
+                        // the ledger is trusted only while its `mtime` still matches, catching a
+                        // manually deleted or truncated file (and letting `repair` force a
+                        // re-download by removing the file first: see chunk2-4's `repair`)
+                        let (expected_hash, expected_size) =
+                            if mode == configuration::InstallableMode::Raw {
+                                (&resource.hash, resource.size)
+                            } else {
+                                let (_, compression_properties) = resource.best_compression();
+                                (compression_properties.hash, compression_properties.size)
+                            };
+                        if let Some(entry) = ledger.completed(&resource_path_id, &index_hash).await
+                        {
+                            if &entry.hash == expected_hash && entry.size == expected_size {
+                                let final_path = if mode == configuration::InstallableMode::Raw {
+                                    path_root.join(&resource_path_id)
+                                } else {
+                                    let (_, compression_properties) = resource.best_compression();
+                                    path_root.join_with_suffix(
+                                        &resource_path_id,
+                                        &compression_properties.suffix.0,
+                                    )
+                                };
+                                if ledger::Entry::modified_secs(&final_path)
+                                    == Some(entry.modified)
+                                {
+                                    continue;
+                                }
+                            }
+                        }
+                    }
                     if force.0
                         || mode != configuration::InstallableMode::Raw
                         || !matches!(
-                            std::fs::metadata(path_root.join(&path_id.join(&resource.name))),
+                            std::fs::metadata(path_root.join(&resource_path_id)),
                             Ok(metadata) if metadata.file_type().is_file()
                         )
                     {
@@ -269,66 +447,300 @@ pub fn install_directory(
                         let server = server.clone();
                         let sender = sender.clone();
                         let path_root = path_root.clone();
-                        let path_id = path_id.join(&resource.name);
+                        let path_id = resource_path_id;
+                        let index_hash = index_hash.clone();
+                        let ledger = ledger.clone();
                         let (compression, compression_properties) = resource.best_compression();
                         let compression = compression.clone();
                         let expected_download_size = compression_properties.size;
                         let expected_download_hash = compression_properties.hash.clone();
                         let suffix = compression_properties.suffix.clone();
+                        let pieces = compression_properties.pieces.clone();
+                        let chunks = resource.chunks.clone();
                         let file_semaphore = file_semaphore.clone();
                         let download_semaphore = download_semaphore.clone();
                         let decode_semaphore = decode_semaphore.clone();
                         let expected_decode_size = resource.size;
                         let expected_decode_hash = resource.hash.clone();
                         join_set.spawn(async move {
-                            server
-                                .download_file(
-                                    &sender,
-                                    path_root.clone(),
-                                    &path_id,
-                                    force,
-                                    Some(expected_download_size),
-                                    Some(expected_download_hash),
-                                    &suffix,
-                                    download_semaphore,
-                                    file_semaphore.clone(),
-                                )
-                                .await?;
-                            if mode == configuration::InstallableMode::Raw {
-                                match compression {
-                                    json_index::Compression::NoneCompression { suffix: _ } => (),
-                                    json_index::Compression::Brotli {
-                                        size: _,
-                                        hash: _,
-                                        suffix: _,
-                                    } => {
-                                        let decode_permit =
-                                            decode_semaphore.acquire_owned().await?;
-                                        let file_permit =
-                                            file_semaphore.acquire_many_owned(2).await?;
-                                        let sender = sender.clone();
-                                        let path_root = path_root.clone();
-                                        let suffix = suffix.clone();
+                            // captured before anything below moves `path_id`/the hashes, so the
+                            // ledger entry can be recorded once this resource is fully verified
+                            let ledger_path_id = path_id.clone();
+                            let (ledger_hash, ledger_size) =
+                                if mode == configuration::InstallableMode::Raw {
+                                    (expected_decode_hash.clone(), expected_decode_size)
+                                } else {
+                                    (expected_download_hash.clone(), expected_download_size)
+                                };
+                            // retried at most once (only when `verify` catches a mismatch and
+                            // `force` allows clobbering the bad file), so a single corrupt
+                            // download or decode does not need to abort the whole install
+                            let verify_attempts = if verify.0 && force.0 { 2 } else { 1 };
+                            // the final file's `mtime` at the moment it finished this install,
+                            // recorded alongside the ledger entry below so a later run can tell
+                            // the file has not been touched since without re-hashing it
+                            let mut ledger_modified = 0u64;
+                            // if every one of this resource's chunks was already deduplicated into
+                            // the content store by some earlier install (of this resource or an
+                            // unrelated one sharing the same bytes), the file can be reassembled
+                            // from disk without ever opening a connection; a chunk the store is
+                            // missing falls straight through to the ordinary download below, since
+                            // the remote only serves whole files/byte-ranges, not individual chunks
+                            if !force.0 && mode == configuration::InstallableMode::Raw {
+                                if let Some(chunk_list) = &chunks {
+                                    let store_root = path_root.chunk_store_root();
+                                    let staged_path = path_root
+                                        .join_with_suffix(&path_id, constants::DOWNLOAD_SUFFIX);
+                                    let assembled = {
+                                        let store_root = store_root.clone();
+                                        let chunk_list = chunk_list.clone();
+                                        let staged_path = staged_path.clone();
+                                        let _permit = file_semaphore.acquire().await?;
                                         tokio::task::spawn_blocking(move || {
-                                            decode::brotli(
-                                                running,
+                                            store::assemble(&store_root, &chunk_list, &staged_path)
+                                        })
+                                        .await
+                                        .map_err(types::ActionError::Join)?
+                                        .map_err(types::ActionError::Directory)?
+                                    };
+                                    if assembled {
+                                        let _permit = file_semaphore.acquire().await?;
+                                        let digest = types::Hash::hasher_from_reader(
+                                            expected_decode_hash.algorithm,
+                                            std::fs::File::open(&staged_path)?,
+                                        )?
+                                        .finalize();
+                                        let size = std::fs::metadata(&staged_path)?.len();
+                                        if digest == expected_decode_hash.digest
+                                            && size == expected_decode_size
+                                        {
+                                            let final_path = path_root.join(&path_id);
+                                            std::fs::rename(&staged_path, &final_path)?;
+                                            sender
+                                                .send(types::Message::Verified {
+                                                    path_id: path_id.clone(),
+                                                })
+                                                .map_err(|_| {
+                                                    types::ActionError::Send(path_id.clone())
+                                                })?;
+                                            ledger
+                                                .record(
+                                                    ledger_path_id,
+                                                    ledger::Entry {
+                                                        index_hash,
+                                                        hash: ledger_hash,
+                                                        size: ledger_size,
+                                                        modified: ledger::Entry::modified_secs(
+                                                            &final_path,
+                                                        )
+                                                        .unwrap_or(0),
+                                                    },
+                                                )
+                                                .await;
+                                            return Ok::<(), types::ActionError>(());
+                                        }
+                                        let _ = std::fs::remove_file(&staged_path);
+                                    }
+                                }
+                            }
+                            for attempt in 0..verify_attempts {
+                                // a compressed variant decoded in-flight (see `download_file`'s
+                                // `codec` argument) lands already decompressed, so the separate
+                                // `decode::decompress` pass below is skipped for it; this only
+                                // applies when `keep` is unset, since keeping the compressed
+                                // variant on disk requires actually downloading it, and (unless
+                                // `force` is set) when no compressed variant is on disk yet, since
+                                // one already there only needs decompressing
+                                let mut decoded_inline = false;
+                                // piece-hashed Range fetches are an HTTP-specific optimization
+                                // (see `Server::is_http`): a `file://`/`sftp://` server has no
+                                // `Transport`-backed equivalent, so it falls back to the ordinary
+                                // whole-file download below even when the index has a `pieces`
+                                // entry for this resource
+                                match &pieces {
+                                    Some(pieces) if server.is_http() => {
+                                        server
+                                            .download_file_pieces(
                                                 &sender,
-                                                path_root,
+                                                path_root.clone(),
                                                 &path_id,
                                                 force,
-                                                keep,
-                                                expected_decode_size,
-                                                &expected_decode_hash,
+                                                expected_download_size,
+                                                &expected_download_hash,
                                                 &suffix,
+                                                pieces,
+                                                download_semaphore.clone(),
+                                                file_semaphore.clone(),
+                                            )
+                                            .await?;
+                                    }
+                                    _ => {
+                                        let codec = if mode == configuration::InstallableMode::Raw
+                                            && !keep.0
+                                            && (force.0
+                                                || !matches!(
+                                                    std::fs::metadata(path_root.join_with_suffix(
+                                                        &path_id,
+                                                        &suffix.0
+                                                    )),
+                                                    Ok(metadata) if metadata.file_type().is_file()
+                                                ))
+                                        {
+                                            decode::Codec::from_compression(&compression)
+                                        } else {
+                                            None
+                                        };
+                                        decoded_inline = codec.is_some();
+                                        server
+                                            .download_file(
+                                                &sender,
+                                                path_root.clone(),
+                                                &path_id,
+                                                force,
+                                                Some(if decoded_inline {
+                                                    expected_decode_size
+                                                } else {
+                                                    expected_download_size
+                                                }),
+                                                Some(if decoded_inline {
+                                                    expected_decode_hash.clone()
+                                                } else {
+                                                    expected_download_hash.clone()
+                                                }),
+                                                &suffix,
+                                                codec,
+                                                download_semaphore.clone(),
+                                                file_semaphore.clone(),
+                                            )
+                                            .await?;
+                                    }
+                                }
+                                if mode == configuration::InstallableMode::Raw {
+                                    let chunk_store_path_id = path_id.clone();
+                                    if !decoded_inline {
+                                        if let Some(codec) =
+                                            decode::Codec::from_compression(&compression)
+                                        {
+                                            let decode_permit =
+                                                decode_semaphore.acquire_owned().await?;
+                                            let file_permit =
+                                                file_semaphore.acquire_many_owned(2).await?;
+                                            let sender = sender.clone();
+                                            let path_root = path_root.clone();
+                                            let suffix = suffix.clone();
+                                            let running = running.clone();
+                                            let decompress_path_id = path_id.clone();
+                                            tokio::task::spawn_blocking(move || {
+                                                decode::decompress(
+                                                    codec,
+                                                    running,
+                                                    &sender,
+                                                    path_root,
+                                                    &decompress_path_id,
+                                                    force,
+                                                    keep,
+                                                    expected_decode_size,
+                                                    &expected_decode_hash,
+                                                    &suffix,
+                                                )?;
+                                                drop(file_permit); // drop tells the compiler to move 'permit' inside the spawn_blocking closure
+                                                drop(decode_permit);
+                                                Ok::<(), types::DecompressError>(())
+                                            })
+                                            .await??;
+                                        }
+                                    }
+                                    if chunks.is_some() {
+                                        let _chunk_file_permit = file_semaphore.acquire().await?;
+                                        let store_root = path_root.chunk_store_root();
+                                        let final_path = path_root.join(&chunk_store_path_id);
+                                        tokio::task::spawn_blocking(move || {
+                                            store::chunk_and_store(
+                                                &store_root,
+                                                std::fs::File::open(&final_path)?,
                                             )?;
-                                            drop(file_permit); // drop tells the compiler to move 'permit' inside the spawn_blocking closure
-                                            drop(decode_permit);
-                                            Ok::<(), types::DecompressError>(())
+                                            Ok::<(), std::io::Error>(())
                                         })
-                                        .await??;
+                                        .await
+                                        .map_err(types::ActionError::Join)?
+                                        .map_err(types::ActionError::Directory)?;
+                                    }
+                                }
+                                let final_path = if mode == configuration::InstallableMode::Raw {
+                                    path_root.join(&path_id)
+                                } else {
+                                    path_root.join_with_suffix(&path_id, &suffix.0)
+                                };
+                                ledger_modified =
+                                    ledger::Entry::modified_secs(&final_path).unwrap_or(0);
+                                if !verify.0 {
+                                    break;
+                                }
+                                // re-hashes the final on-disk file independently of the checks
+                                // `download_file`/`download_file_pieces`/`decompress` already made
+                                // while writing it, to catch corruption introduced afterwards
+                                let (verify_path, verify_expected_hash) = (
+                                    final_path,
+                                    if mode == configuration::InstallableMode::Raw {
+                                        expected_decode_hash.clone()
+                                    } else {
+                                        expected_download_hash.clone()
+                                    },
+                                );
+                                let actual_hash = {
+                                    let _permit = file_semaphore.acquire().await?;
+                                    let file = std::fs::File::open(&verify_path)?;
+                                    types::Hash {
+                                        algorithm: verify_expected_hash.algorithm,
+                                        digest: types::Hash::hasher_from_reader(
+                                            verify_expected_hash.algorithm,
+                                            file,
+                                        )?
+                                        .finalize(),
                                     }
+                                };
+                                if actual_hash == verify_expected_hash {
+                                    sender
+                                        .send(types::Message::Verified {
+                                            path_id: path_id.clone(),
+                                        })
+                                        .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                                    break;
+                                }
+                                sender
+                                    .send(types::Message::VerifyFailed {
+                                        path_id: path_id.clone(),
+                                        expected: verify_expected_hash.clone(),
+                                        actual: actual_hash.clone(),
+                                    })
+                                    .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                                if !force.0 || attempt + 1 >= verify_attempts {
+                                    return Err(types::ActionError::Download(
+                                        types::DownloadError::Hash {
+                                            path_id: path_id.clone(),
+                                            expected: verify_expected_hash,
+                                            downloaded: actual_hash,
+                                        },
+                                    ));
+                                }
+                                let _ = std::fs::remove_file(&verify_path);
+                                if mode == configuration::InstallableMode::Raw {
+                                    let _ = std::fs::remove_file(
+                                        path_root.join_with_suffix(&path_id, &suffix.0),
+                                    );
                                 }
                             }
+                            ledger
+                                .record(
+                                    ledger_path_id,
+                                    ledger::Entry {
+                                        index_hash,
+                                        hash: ledger_hash,
+                                        size: ledger_size,
+                                        modified: ledger_modified,
+                                    },
+                                )
+                                .await;
                             Ok::<(), types::ActionError>(())
                         });
                     }
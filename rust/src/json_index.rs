@@ -8,16 +8,60 @@ pub struct Version {
     pub patch: u64,
 }
 
+// the piece hashes cover the bytes of the compression variant they are attached to
+// (the compressed bytes for `Brotli`/`Zstd`, the raw bytes for `NoneCompression`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Pieces {
+    pub length: u64,
+    pub hashes: Vec<types::Hash>,
+}
+
+/// How a resource's bytes were encoded for transfer. Besides `NoneCompression` (served as-is),
+/// each codec variant carries the compressed variant's own `size`/`hash`/`suffix` so
+/// `best_compression` can compare candidates by transfer size alone; `Resource::size`/`hash`
+/// always describe the decompressed bytes regardless of which variant is picked. Publishers
+/// choose a codec per resource — `Zstd`'s larger compression window suits big recordings better
+/// than `Brotli`, which remains the most common choice for everyday datasets.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum Compression {
     #[serde(rename = "none")]
-    NoneCompression { suffix: types::Name },
+    NoneCompression {
+        suffix: types::Name,
+        #[serde(default)]
+        pieces: Option<Pieces>,
+    },
     #[serde(rename = "brotli")]
     Brotli {
         size: u64,
         hash: types::Hash,
         suffix: types::Name,
+        #[serde(default)]
+        pieces: Option<Pieces>,
+    },
+    #[serde(rename = "zstd")]
+    Zstd {
+        size: u64,
+        hash: types::Hash,
+        suffix: types::Name,
+        #[serde(default)]
+        pieces: Option<Pieces>,
+    },
+    #[serde(rename = "xz")]
+    Xz {
+        size: u64,
+        hash: types::Hash,
+        suffix: types::Name,
+        #[serde(default)]
+        pieces: Option<Pieces>,
+    },
+    #[serde(rename = "gzip")]
+    Gzip {
+        size: u64,
+        hash: types::Hash,
+        suffix: types::Name,
+        #[serde(default)]
+        pieces: Option<Pieces>,
     },
 }
 
@@ -74,6 +118,14 @@ impl<'de> serde::Deserialize<'de> for Compressions {
     }
 }
 
+// content-defined chunk of the resource's raw (decompressed) bytes, used to deduplicate
+// identical chunks (repeated headers, near-identical recordings, ...) across resources
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Chunk {
+    pub hash: types::Hash,
+    pub length: u64,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Resource {
     pub name: types::Name,
@@ -81,12 +133,15 @@ pub struct Resource {
     pub hash: types::Hash,
     pub compressions: Compressions,
     pub doi: Option<types::Doi>,
+    #[serde(default)]
+    pub chunks: Option<Vec<Chunk>>,
 }
 
 pub struct CompressionProperties<'a> {
     pub size: u64,
     pub hash: &'a types::Hash,
     pub suffix: &'a types::Name,
+    pub pieces: &'a Option<Pieces>,
 }
 
 impl Resource {
@@ -95,19 +150,61 @@ impl Resource {
         compression: &'a Compression,
     ) -> CompressionProperties<'a> {
         match compression {
-            Compression::NoneCompression { suffix } => CompressionProperties {
+            Compression::NoneCompression { suffix, pieces } => CompressionProperties {
                 size: self.size,
                 hash: &self.hash,
                 suffix,
+                pieces,
+            },
+            Compression::Brotli {
+                size,
+                hash,
+                suffix,
+                pieces,
+            } => CompressionProperties {
+                size: *size,
+                hash,
+                suffix,
+                pieces,
+            },
+            Compression::Zstd {
+                size,
+                hash,
+                suffix,
+                pieces,
+            } => CompressionProperties {
+                size: *size,
+                hash,
+                suffix,
+                pieces,
+            },
+            Compression::Xz {
+                size,
+                hash,
+                suffix,
+                pieces,
+            } => CompressionProperties {
+                size: *size,
+                hash,
+                suffix,
+                pieces,
             },
-            Compression::Brotli { size, hash, suffix } => CompressionProperties {
+            Compression::Gzip {
+                size,
+                hash,
+                suffix,
+                pieces,
+            } => CompressionProperties {
                 size: *size,
                 hash,
                 suffix,
+                pieces,
             },
         }
     }
 
+    /// Picks the smallest compressed variant by transfer size, regardless of codec; ties keep
+    /// whichever variant was seen first (`compressions.first`, then `compressions.rest` in order).
     pub fn best_compression(&self) -> (&Compression, CompressionProperties) {
         self.compressions.rest.iter().fold(
             (
@@ -161,3 +258,48 @@ pub struct Index {
     pub other_files: Vec<OtherFile>,
     pub metadata: Option<serde_json::Value>,
 }
+
+// magic + version header prefixed to the CBOR body, so that index bytes are self-describing and
+// the loader can tell a CBOR index apart from a plain JSON one by content alone; the index is
+// always fetched and stored as `-index.json`, whichever encoding it holds
+const CBOR_HEADER: &[u8; 5] = b"UNDR\x01";
+
+impl Index {
+    pub fn to_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut bytes = CBOR_HEADER.to_vec();
+        ciborium::ser::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Decodes an index, sniffing the CBOR header to pick the decoder. Large directory trees
+    /// (tens of thousands of resources) parse noticeably faster from CBOR than from JSON.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Index, types::ActionError> {
+        match bytes.strip_prefix(CBOR_HEADER) {
+            Some(body) => Ok(ciborium::de::from_reader(body)?),
+            None => Ok(serde_json::from_slice(bytes)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_index_cbor_round_trip() {
+        let index = crate::json_index::Index {
+            version: crate::json_index::Version {
+                major: 1,
+                minor: 0,
+                patch: 0,
+            },
+            doi: None,
+            directories: vec![crate::types::Name("child".to_owned())],
+            files: Vec::new(),
+            other_files: Vec::new(),
+            metadata: None,
+        };
+        let cbor = index.to_cbor().unwrap();
+        let decoded = crate::json_index::Index::from_bytes(&cbor).unwrap();
+        assert_eq!(decoded.version.major, index.version.major);
+        assert_eq!(decoded.directories, index.directories);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::types;
+
+/// One verified install recorded for a resource's final on-disk file: the decoded (`Raw`) or
+/// downloaded (`Local`) hash/size that was checked right after that file landed, plus the hash of
+/// the `-index.json` bytes it came from and the file's `mtime` at that moment. A later run only
+/// trusts the entry while the directory's index still hashes the same way, so a republished or
+/// edited index silently drops whatever it used to say about that resource instead of serving a
+/// stale skip; `modified` additionally guards against the file itself having been deleted,
+/// truncated or replaced since, since a republished index is not the only way the on-disk state
+/// can drift from what the ledger remembers.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub index_hash: types::Hash,
+    pub hash: types::Hash,
+    pub size: u64,
+    pub modified: u64,
+}
+
+impl Entry {
+    /// Seconds since `UNIX_EPOCH` for `path`'s current `mtime`, or `None` if the file is gone or
+    /// the platform cannot report a modification time.
+    pub fn modified_secs(path: &std::path::Path) -> Option<u64> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+    }
+}
+
+/// In-memory form of the per-root ledger, keyed by `path_id` (`PathId::0`).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct Ledger {
+    entries: std::collections::HashMap<String, Entry>,
+}
+
+/// Handle shared across every `install_directory` task for one install. Guards the `Ledger` with
+/// a mutex (updates are infrequent next to the download/decode work each one follows) and
+/// persists it to `path` after every change; the whole file is rewritten on each save, the same
+/// trade-off `download_file_pieces` already makes for its `.bitfield` sidecars.
+#[derive(Clone)]
+pub struct SharedLedger {
+    path: std::sync::Arc<std::path::PathBuf>,
+    ledger: std::sync::Arc<tokio::sync::Mutex<Ledger>>,
+}
+
+impl SharedLedger {
+    pub fn load(path: std::path::PathBuf) -> SharedLedger {
+        let ledger = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        SharedLedger {
+            path: std::sync::Arc::new(path),
+            ledger: std::sync::Arc::new(tokio::sync::Mutex::new(ledger)),
+        }
+    }
+
+    /// `Some` only while `index_hash` still matches the directory the caller just re-read; a
+    /// mismatch means the resource's index entry may have changed since the entry was recorded,
+    /// so the caller falls back to its usual (slower) up-to-date check instead of trusting it.
+    pub async fn completed(&self, path_id: &types::PathId, index_hash: &types::Hash) -> Option<Entry> {
+        self.ledger
+            .lock()
+            .await
+            .entries
+            .get(&path_id.0)
+            .filter(|entry| &entry.index_hash == index_hash)
+            .cloned()
+    }
+
+    /// Records `entry` for `path_id` and rewrites the ledger file. A failure to persist is not
+    /// fatal: the resource itself is already complete on disk, so a future run just re-verifies
+    /// it the slow way instead of trusting a ledger entry that never made it to disk.
+    pub async fn record(&self, path_id: types::PathId, entry: Entry) {
+        let mut ledger = self.ledger.lock().await;
+        ledger.entries.insert(path_id.0, entry);
+        if let Ok(content) = serde_json::to_vec(&*ledger) {
+            let _ = std::fs::write(self.path.as_path(), content);
+        }
+    }
+
+    /// Drops `path_id`'s entry, if any, and rewrites the ledger file. `repair` calls this right
+    /// after deleting a resource's on-disk artifact, so the next `install` cannot skip it on a
+    /// stale ledger hit recorded before the file was removed out from under it.
+    pub async fn invalidate(&self, path_id: &types::PathId) {
+        let mut ledger = self.ledger.lock().await;
+        if ledger.entries.remove(&path_id.0).is_some() {
+            if let Ok(content) = serde_json::to_vec(&*ledger) {
+                let _ = std::fs::write(self.path.as_path(), content);
+            }
+        }
+    }
+}
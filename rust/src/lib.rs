@@ -7,13 +7,23 @@ mod constants;
 mod decode;
 mod install_directory;
 mod json_index;
+mod ledger;
+mod mount;
+mod plan;
 mod remote;
+mod storage;
+mod store;
+mod transport;
 mod types;
+mod verify;
 pub use configuration::Configuration;
 pub use configuration::ConfigurationError;
 pub use configuration::Mode;
 pub use types::ActionError;
+pub use types::AdaptiveSemaphore;
 pub use types::CalculateSize;
+pub use types::CitationFormat;
+pub use types::ContinueOnError;
 pub use types::DecodePermits;
 pub use types::DispatchDois;
 pub use types::DownloadDoiPermits;
@@ -21,73 +31,113 @@ pub use types::DownloadIndexPermits;
 pub use types::DownloadPermits;
 pub use types::FilePermits;
 pub use types::Force;
+pub use types::InstallControl;
 pub use types::Keep;
+pub use mount::MountError;
+pub use plan::InstallPlan;
+pub use plan::PlanAction;
+pub use plan::PlanCounts;
+pub use plan::PlanDataset;
+pub use plan::PlanDirectory;
+pub use plan::PlanFile;
 pub use types::Message;
 pub use types::Pretty;
+pub use types::RetryPolicy;
+pub use types::RunControl;
+pub use types::UninstallFailure;
+pub use types::Verify;
+pub use types::VerifyMismatch;
 
 impl Configuration {
     /// Download index files and download / decompress data files for local / raw datasets
     ///
     /// # Arguments
     ///
-    /// * `running` -
+    /// * `control` - lets the caller stop, pause/resume, and retune download/decode concurrency
+    ///   for the install from another task
     /// * `handle_message` -
     /// * `force` - download and decompress even if the files are already present
     /// * `keep` - do not delete compressed files after decompressing
-    /// * `download_permits` -
-    /// * `decode_permits` -
+    /// * `verify` - re-hash each resource's final on-disk file after download/decode and compare
+    ///   it against the index's expected digest, reporting `Message::Verified`/
+    ///   `Message::VerifyFailed`; combined with `force`, a mismatch is retried once from scratch
+    ///   instead of aborting the install
+    /// * `continue_on_error` - keep installing every other dataset after one dataset's task
+    ///   fails, instead of stopping all of them; failures are reported as they happen via
+    ///   `Message::TaskFailed` and returned together as `ActionError::Partial` once every dataset
+    ///   has finished
+    /// * `retry_policy` - bounds retry attempts/backoff for index and data file downloads;
+    ///   `None` uses `types::RetryPolicy::default()`
     #[allow(clippy::too_many_arguments)]
     pub async fn install<HandleMessage>(
         &self,
-        running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        control: types::InstallControl,
         mut handle_message: HandleMessage,
         force: Force,
         keep: Keep,
         dispatch_dois: DispatchDois,
         calculate_size: CalculateSize,
+        verify: Verify,
+        continue_on_error: ContinueOnError,
         file_permits: FilePermits,
         download_index_permits: DownloadIndexPermits,
-        download_permits: DownloadPermits,
-        decode_permits: DecodePermits,
+        retry_policy: Option<types::RetryPolicy>,
     ) -> Result<(), ActionError>
     where
         HandleMessage: FnMut(Message),
     {
-        std::fs::create_dir_all(&self.directory).map_err(ActionError::Directory)?;
-        let path_root = types::PathRoot(std::sync::Arc::<std::path::PathBuf>::from(
-            self.directory.clone(),
-        ));
+        if self.directory_url.is_none() {
+            std::fs::create_dir_all(&self.directory).map_err(ActionError::Directory)?;
+        }
+        let retry_policy = retry_policy.unwrap_or_default();
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let ledger = ledger::SharedLedger::load(path_root.ledger_path());
         let mut join_set = tokio::task::JoinSet::new();
         let file_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(file_permits.0 - 1));
-        let download_index_semaphore =
-            std::sync::Arc::new(tokio::sync::Semaphore::new(download_index_permits.0));
-        let download_semaphore =
-            std::sync::Arc::new(tokio::sync::Semaphore::new(download_permits.0));
-        let decode_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(decode_permits.0));
+        let download_index_semaphore = std::sync::Arc::new(types::AdaptiveSemaphore::new(
+            download_index_permits.0,
+        ));
+        let running = control.run_control();
+        let download_semaphore = control.download_semaphore();
+        let decode_semaphore = control.decode_semaphore();
         let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
         for (dataset, mode) in self.datasets.iter().filter_map(|dataset| {
             configuration::InstallableMode::try_from(dataset.mode)
                 .ok()
                 .map(|mode| (dataset, mode))
         }) {
-            join_set.spawn(install_directory::install_directory(
+            let dataset_path_id = types::PathId(dataset.name.0.clone());
+            let install_directory = install_directory::install_directory(
                 running.clone(),
-                remote::Server::new(&dataset.url, &dataset.timeout)?,
+                remote::Server::new(
+                    &dataset.url,
+                    &dataset.timeout,
+                    &dataset.max_retries,
+                    &dataset.max_bytes_per_second,
+                    &retry_policy,
+                )?,
                 sender.clone(),
                 path_root.clone(),
-                types::PathId(dataset.name.0.clone()),
+                dataset_path_id.clone(),
                 force,
                 keep,
                 dispatch_dois,
                 calculate_size,
+                verify,
                 mode,
                 file_semaphore.clone(),
                 download_index_semaphore.clone(),
                 download_semaphore.clone(),
                 decode_semaphore.clone(),
-            ));
+                ledger.clone(),
+            );
+            join_set.spawn(async move { (dataset_path_id, install_directory.await) });
         }
         drop(sender);
+        let mut failures = Vec::new();
         loop {
             tokio::select! {
                 biased;
@@ -96,15 +146,21 @@ impl Configuration {
                 }
                 Some(task) = join_set.join_next() => {
                     match task {
-                        Ok(result) => match result {
-                            Ok(()) => (),
-                            Err(error) => {
-                                running.store(false, std::sync::atomic::Ordering::Release);
+                        Ok((_, Ok(()))) => (),
+                        Ok((path_id, Err(error))) => {
+                            if continue_on_error.0 {
+                                handle_message(Message::TaskFailed {
+                                    path_id: path_id.clone(),
+                                    error: error.to_string(),
+                                });
+                                failures.push((path_id, error));
+                            } else {
+                                running.stop();
                                 return Err(error);
-                            },
+                            }
                         },
                         Err(error) => {
-                            running.store(false, std::sync::atomic::Ordering::Release);
+                            running.stop();
                             return Err(ActionError::Join(error));
                         }
                     }
@@ -112,13 +168,17 @@ impl Configuration {
                 else => break,
             }
         }
-        Ok(())
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ActionError::Partial(failures))
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
     pub async fn bibtex<HandleMessage, P: AsRef<std::path::Path>>(
         &self,
-        running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        running: types::RunControl,
         mut handle_message: HandleMessage,
         force: Force,
         file_permits: FilePermits,
@@ -126,43 +186,70 @@ impl Configuration {
         download_doi_permits: DownloadDoiPermits,
         doi_timeout: Option<f64>,
         output_path: P,
+        format: Option<types::CitationFormat>,
         pretty: Pretty,
+        continue_on_error: ContinueOnError,
+        retry_policy: Option<types::RetryPolicy>,
     ) -> Result<(), ActionError>
     where
         HandleMessage: FnMut(Message),
     {
-        std::fs::create_dir_all(&self.directory).map_err(ActionError::Directory)?;
-        let path_root = types::PathRoot(std::sync::Arc::<std::path::PathBuf>::from(
-            self.directory.clone(),
-        ));
+        if self.directory_url.is_none() {
+            std::fs::create_dir_all(&self.directory).map_err(ActionError::Directory)?;
+        }
+        let retry_policy = retry_policy.unwrap_or_default();
+        let format = format
+            .or_else(|| {
+                output_path
+                    .as_ref()
+                    .extension()
+                    .and_then(types::CitationFormat::from_extension)
+            })
+            .unwrap_or(types::CitationFormat::BibTex);
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let ledger = ledger::SharedLedger::load(path_root.ledger_path());
         let mut join_set = tokio::task::JoinSet::new();
-        let download_index_semaphore =
-            std::sync::Arc::new(tokio::sync::Semaphore::new(download_index_permits.0));
+        let download_index_semaphore = std::sync::Arc::new(types::AdaptiveSemaphore::new(
+            download_index_permits.0,
+        ));
         let file_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(file_permits.0));
-        let download_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(1));
-        let decode_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(1));
+        let download_semaphore = std::sync::Arc::new(types::AdaptiveSemaphore::new(1));
+        let decode_semaphore = std::sync::Arc::new(types::AdaptiveSemaphore::new(1));
         let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
         for (dataset, _mode) in self.datasets.iter().filter_map(|dataset| {
             configuration::InstallableMode::try_from(dataset.mode)
                 .ok()
                 .map(|mode| (dataset, mode))
         }) {
-            join_set.spawn(install_directory::install_directory(
+            let dataset_path_id = types::PathId(dataset.name.0.clone());
+            let install_directory = install_directory::install_directory(
                 running.clone(),
-                remote::Server::new(&dataset.url, &dataset.timeout)?,
+                remote::Server::new(
+                    &dataset.url,
+                    &dataset.timeout,
+                    &dataset.max_retries,
+                    &dataset.max_bytes_per_second,
+                    &retry_policy,
+                )?,
                 sender.clone(),
                 path_root.clone(),
-                types::PathId(dataset.name.0.clone()),
+                dataset_path_id.clone(),
                 force,
                 Keep(false),
                 DispatchDois(true),
                 CalculateSize(false),
+                Verify(false),
                 configuration::InstallableMode::Remote,
                 file_semaphore.clone(),
                 download_index_semaphore.clone(),
                 download_semaphore.clone(),
                 decode_semaphore.clone(),
-            ));
+                ledger.clone(),
+            );
+            join_set.spawn(async move { (dataset_path_id, install_directory.await) });
         }
         let mut doi_to_path_ids_and_content =
             std::collections::HashMap::<types::Doi, (Vec<types::PathId>, Option<String>)>::new();
@@ -193,6 +280,7 @@ impl Configuration {
                     })
             })
             .collect::<Vec<DatasetProgress>>();
+        let mut failures = Vec::new();
         loop {
             tokio::select! {
                 biased;
@@ -208,7 +296,9 @@ impl Configuration {
                                 let file_semaphore = file_semaphore.clone();
                                 let download_doi_semaphore = download_doi_semaphore.clone();
                                 let sender = sender.as_ref().unwrap().clone();
+                                let doi_path_id = types::PathId(format!("doi:{}", value.0));
                                 join_set.spawn(async move {
+                                    let result: Result<(), ActionError> = async {
                                     let _download_doi_permit = download_doi_semaphore
                                         .acquire()
                                         .await?;
@@ -219,48 +309,85 @@ impl Configuration {
                                             status: types::DoiStatus::Start,
                                         })
                                         .map_err(|_| ActionError::DoiSend)?;
-                                    let response = match client
-                                        .get(format!("https://doi.org/{}", &value.0))
-                                        .header(
-                                            reqwest::header::ACCEPT,
-                                            "application/x-bibtex; charset=utf-8",
-                                        )
-                                        .send()
-                                        .await {
-                                        Ok(response) => response,
-                                        Err(error) => {
+                                    // retries a transient failure (connect/timeout, 5xx, 429) up to
+                                    // `retry_policy.max_attempts` times with backoff, honoring a
+                                    // numeric `Retry-After` on 429; any other error is permanent
+                                    let mut attempt = 0;
+                                    let content = loop {
+                                        let response = match client
+                                            .get(format!("https://doi.org/{}", &value.0))
+                                            .header(reqwest::header::ACCEPT, format.accept_header())
+                                            .send()
+                                            .await {
+                                            Ok(response) => response,
+                                            Err(error) => {
+                                                if attempt < retry_policy.max_attempts
+                                                    && (error.is_connect() || error.is_timeout() || error.is_request())
+                                                {
+                                                    sender
+                                                        .send(Message::DoiProgress {
+                                                            value: value.clone(),
+                                                            status: types::DoiStatus::Retrying {
+                                                                attempt: attempt + 1,
+                                                                max_attempts: retry_policy.max_attempts,
+                                                            },
+                                                        })
+                                                        .map_err(|_| ActionError::DoiSend)?;
+                                                    tokio::time::sleep(retry_policy.delay(attempt)).await;
+                                                    attempt += 1;
+                                                    continue;
+                                                }
+                                                sender
+                                                    .send(Message::DoiProgress {
+                                                        value: value.clone(),
+                                                        status: types::DoiStatus::Error(format!("{error:?}")),
+                                                    })
+                                                    .map_err(|_| ActionError::DoiSend)?;
+                                                return Ok(());
+                                            },
+                                        };
+                                        let status = response.status();
+                                        if types::RetryPolicy::is_retryable_status(status)
+                                            && attempt < retry_policy.max_attempts
+                                        {
+                                            let delay = retry_policy.delay_for_response(attempt, response.headers());
                                             sender
                                                 .send(Message::DoiProgress {
                                                     value: value.clone(),
-                                                    status: types::DoiStatus::Error(format!("{error:?}")),
+                                                    status: types::DoiStatus::Retrying {
+                                                        attempt: attempt + 1,
+                                                        max_attempts: retry_policy.max_attempts,
+                                                    },
                                                 })
                                                 .map_err(|_| ActionError::DoiSend)?;
-                                            return Ok(());
-                                        },
-                                    };
-                                    let status = response.status();
-                                    let content = match response.text().await {
-                                        Ok(content) => content,
-                                        Err(error) => {
+                                            tokio::time::sleep(delay).await;
+                                            attempt += 1;
+                                            continue;
+                                        }
+                                        let content = match response.text().await {
+                                            Ok(content) => content,
+                                            Err(error) => {
+                                                sender
+                                                    .send(Message::DoiProgress {
+                                                        value: value.clone(),
+                                                        status: types::DoiStatus::Error(format!("{error:?}")),
+                                                    })
+                                                    .map_err(|_| ActionError::DoiSend)?;
+                                                return Ok(());
+                                            },
+                                        };
+                                        if status.is_client_error() || status.is_server_error() {
                                             sender
                                                 .send(Message::DoiProgress {
                                                     value: value.clone(),
-                                                    status: types::DoiStatus::Error(format!("{error:?}")),
+                                                    status: types::DoiStatus::Error(content),
                                                 })
                                                 .map_err(|_| ActionError::DoiSend)?;
                                             return Ok(());
-                                        },
+                                        }
+                                        break content;
                                     };
-                                    if status.is_client_error() || status.is_server_error() {
-                                        sender
-                                            .send(Message::DoiProgress {
-                                                value: value.clone(),
-                                                status: types::DoiStatus::Error(content),
-                                            })
-                                            .map_err(|_| ActionError::DoiSend)?;
-                                        return Ok(());
-                                    }
-                                    if pretty.0 {
+                                    if pretty.0 && format == types::CitationFormat::BibTex {
                                         sender
                                             .send(Message::DoiProgress {
                                                 value: value.clone(),
@@ -276,6 +403,8 @@ impl Configuration {
                                             .map_err(|_| ActionError::DoiSend)?;
                                     }
                                     Ok::<(), ActionError>(())
+                                    }.await;
+                                    (doi_path_id, result)
                                 });
                             }
                         }
@@ -283,6 +412,7 @@ impl Configuration {
                         handle_message(Message::DoiProgress {value: value.clone(), status: status.clone()});
                         match status {
                             types::DoiStatus::Start => {},
+                            types::DoiStatus::Retrying { .. } => {},
                             types::DoiStatus::Success(content) => {
                                 doi_to_path_ids_and_content.get_mut(&value).unwrap().1 = Some(content);
                             },
@@ -290,7 +420,7 @@ impl Configuration {
                                 doi_to_path_ids_and_content.get_mut(&value).unwrap().1 = Some(error);
                             },
                         }
-                        bibtex::write(&output_path, &doi_to_path_ids_and_content)?;
+                        bibtex::write(&output_path, &doi_to_path_ids_and_content, format)?;
                     } else if let Message::IndexLoaded {path_id, children} = message {
                         handle_message(Message::IndexLoaded { path_id: path_id.clone(), children });
                         for dataset_progress in datasets_progress.iter_mut() {
@@ -336,15 +466,174 @@ impl Configuration {
                 }
                 Some(task) = join_set.join_next() => {
                     match task {
-                        Ok(result) => match result {
-                            Ok(()) => (),
-                            Err(error) => {
-                                running.store(false, std::sync::atomic::Ordering::Release);
+                        Ok((_, Ok(()))) => (),
+                        Ok((path_id, Err(error))) => {
+                            if continue_on_error.0 {
+                                handle_message(Message::TaskFailed {
+                                    path_id: path_id.clone(),
+                                    error: error.to_string(),
+                                });
+                                failures.push((path_id, error));
+                            } else {
+                                running.stop();
                                 return Err(error);
-                            },
+                            }
                         },
                         Err(error) => {
-                            running.store(false, std::sync::atomic::Ordering::Release);
+                            running.stop();
+                            return Err(ActionError::Join(error));
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ActionError::Partial(failures))
+        }
+    }
+
+    /// Deletes the installed files of every non-disabled dataset, plus their index files and any
+    /// directory left empty afterward. Unlike `install`, a filesystem failure on one file does
+    /// not abort the walk: failures are collected and returned once every dataset has been
+    /// visited, with per-file progress (success or failure) reported through `handle_message` as
+    /// it happens.
+    pub async fn uninstall<HandleMessage>(
+        &self,
+        running: types::RunControl,
+        mut handle_message: HandleMessage,
+        file_permits: FilePermits,
+    ) -> Result<Vec<types::UninstallFailure>, ActionError>
+    where
+        HandleMessage: FnMut(Message),
+    {
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let mut join_set = tokio::task::JoinSet::new();
+        let file_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(file_permits.0));
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for dataset in self
+            .datasets
+            .iter()
+            .filter(|dataset| dataset.mode != Mode::Disabled)
+        {
+            join_set.spawn(install_directory::uninstall_directory(
+                running.clone(),
+                sender.clone(),
+                path_root.clone(),
+                types::PathId(dataset.name.0.clone()),
+                file_semaphore.clone(),
+            ));
+        }
+        drop(sender);
+        let mut failures = Vec::new();
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = receiver.recv() => {
+                    handle_message(message);
+                }
+                Some(task) = join_set.join_next() => {
+                    match task {
+                        Ok(result) => failures.extend(result?),
+                        Err(error) => {
+                            running.stop();
+                            return Err(ActionError::Join(error));
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Classifies what `install` would do to every resource of every non-disabled dataset,
+    /// without downloading or decoding any of them (only the small `-index.json` files are
+    /// fetched, to know what the server has). Lets the caller show a confirmation tree before
+    /// committing to a potentially large download.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn plan<HandleMessage>(
+        &self,
+        running: types::RunControl,
+        mut handle_message: HandleMessage,
+        force: Force,
+        file_permits: FilePermits,
+        download_index_permits: DownloadIndexPermits,
+        retry_policy: Option<types::RetryPolicy>,
+    ) -> Result<plan::InstallPlan, ActionError>
+    where
+        HandleMessage: FnMut(Message),
+    {
+        if self.directory_url.is_none() {
+            std::fs::create_dir_all(&self.directory).map_err(ActionError::Directory)?;
+        }
+        let retry_policy = retry_policy.unwrap_or_default();
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let mut join_set = tokio::task::JoinSet::new();
+        let file_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(file_permits.0));
+        let download_index_semaphore = std::sync::Arc::new(types::AdaptiveSemaphore::new(
+            download_index_permits.0,
+        ));
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for (dataset, mode) in self.datasets.iter().filter_map(|dataset| {
+            configuration::InstallableMode::try_from(dataset.mode)
+                .ok()
+                .map(|mode| (dataset, mode))
+        }) {
+            let name = dataset.name.clone();
+            let running = running.clone();
+            let server = remote::Server::new(
+                &dataset.url,
+                &dataset.timeout,
+                &dataset.max_retries,
+                &dataset.max_bytes_per_second,
+                &retry_policy,
+            )?;
+            let sender = sender.clone();
+            let path_root = path_root.clone();
+            let path_id = types::PathId(name.0.clone());
+            let file_semaphore = file_semaphore.clone();
+            let download_index_semaphore = download_index_semaphore.clone();
+            join_set.spawn(async move {
+                let root = plan::plan_directory(
+                    running,
+                    server,
+                    sender,
+                    path_root,
+                    path_id,
+                    force,
+                    mode,
+                    file_semaphore,
+                    download_index_semaphore,
+                )
+                .await?;
+                Ok::<(types::Name, plan::PlanDirectory), ActionError>((name, root))
+            });
+        }
+        drop(sender);
+        let mut datasets = Vec::new();
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = receiver.recv() => {
+                    handle_message(message);
+                }
+                Some(task) = join_set.join_next() => {
+                    match task {
+                        Ok(result) => {
+                            let (name, root) = result?;
+                            datasets.push(plan::PlanDataset { name, root });
+                        }
+                        Err(error) => {
+                            running.stop();
                             return Err(ActionError::Join(error));
                         }
                     }
@@ -352,6 +641,177 @@ impl Configuration {
                 else => break,
             }
         }
-        Ok(())
+        let mut total = plan::PlanCounts::default();
+        for dataset in &datasets {
+            total.extend(&dataset.root.counts);
+        }
+        Ok(plan::InstallPlan {
+            version: plan::VERSION,
+            datasets,
+            total,
+        })
+    }
+
+    /// Recomputes and compares the hash of every locally-present resource of every non-disabled
+    /// dataset against its index entry, without touching the network. Per-file pass/fail is
+    /// reported through `handle_message` as it happens; the returned list is every resource that
+    /// was missing or did not match, ready to be handed to `repair`.
+    pub async fn verify<HandleMessage>(
+        &self,
+        running: types::RunControl,
+        mut handle_message: HandleMessage,
+        file_permits: FilePermits,
+    ) -> Result<Vec<types::VerifyMismatch>, ActionError>
+    where
+        HandleMessage: FnMut(Message),
+    {
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let mut join_set = tokio::task::JoinSet::new();
+        let file_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(file_permits.0));
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for (dataset, mode) in self.datasets.iter().filter_map(|dataset| {
+            configuration::InstallableMode::try_from(dataset.mode)
+                .ok()
+                .map(|mode| (dataset, mode))
+        }) {
+            join_set.spawn(verify::verify_directory(
+                running.clone(),
+                sender.clone(),
+                path_root.clone(),
+                types::PathId(dataset.name.0.clone()),
+                mode,
+                file_semaphore.clone(),
+            ));
+        }
+        drop(sender);
+        let mut mismatches = Vec::new();
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = receiver.recv() => {
+                    handle_message(message);
+                }
+                Some(task) = join_set.join_next() => {
+                    match task {
+                        Ok(result) => mismatches.extend(result?),
+                        Err(error) => {
+                            running.stop();
+                            return Err(ActionError::Join(error));
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Deletes every resource named in `mismatches` (as produced by `verify`) and then runs a
+    /// regular, non-`Force`d `install`, so only those damaged files are re-downloaded while
+    /// everything already intact is left alone.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn repair<HandleMessage>(
+        &self,
+        control: types::InstallControl,
+        handle_message: HandleMessage,
+        mismatches: Vec<String>,
+        keep: Keep,
+        file_permits: FilePermits,
+        download_index_permits: DownloadIndexPermits,
+        retry_policy: Option<types::RetryPolicy>,
+    ) -> Result<(), ActionError>
+    where
+        HandleMessage: FnMut(Message),
+    {
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        let ledger = ledger::SharedLedger::load(path_root.ledger_path());
+        for path_id in &mismatches {
+            let resource_path_id = types::PathId(path_id.clone());
+            // a mismatch only carries the bare resource path, so the dataset it belongs to (and
+            // therefore its mode) has to be recovered from the path's own leading segment before
+            // the right on-disk artifact can be resolved
+            let mode = self
+                .datasets
+                .iter()
+                .find(|dataset| {
+                    resource_path_id.0 == dataset.name.0
+                        || resource_path_id
+                            .0
+                            .starts_with(&format!("{}/", dataset.name.0))
+                })
+                .and_then(|dataset| configuration::InstallableMode::try_from(dataset.mode).ok())
+                .unwrap_or(configuration::InstallableMode::Raw);
+            match verify::resource_artifact(&path_root, &resource_path_id, mode)? {
+                Some(artifact) => {
+                    // a file that is already gone is not an error here: `install` will simply
+                    // download it; the `.download`/bitfield sidecars are cleaned up too so a
+                    // fresh download does not mistake a stale partial for one already in progress
+                    let _ = std::fs::remove_file(&artifact.path);
+                    let _ = std::fs::remove_file(path_root.join_with_suffixes(
+                        &resource_path_id,
+                        &artifact.suffix.0,
+                        constants::DOWNLOAD_SUFFIX,
+                    ));
+                    let _ = std::fs::remove_file(path_root.join_with_suffixes(
+                        &resource_path_id,
+                        &artifact.suffix.0,
+                        constants::BITFIELD_SUFFIX,
+                    ));
+                }
+                // no index entry left to resolve a compression suffix from: fall back to the
+                // bare path, which is at least correct for `Raw` datasets
+                None => {
+                    let _ = std::fs::remove_file(path_root.join(&resource_path_id));
+                }
+            }
+            // the ledger entry must go too: its hash/size/mtime were recorded for the file that
+            // was just deleted, and leaving it in place would make the `install` below skip this
+            // exact resource on the same stale-hit check that let it go unrepaired in the first
+            // place
+            ledger.invalidate(&resource_path_id).await;
+        }
+        self.install(
+            control,
+            handle_message,
+            Force(false),
+            keep,
+            DispatchDois(false),
+            CalculateSize(false),
+            Verify(false),
+            ContinueOnError(false),
+            file_permits,
+            download_index_permits,
+            retry_policy,
+        )
+        .await
+    }
+
+    /// Exposes one already-installed dataset as a read-only FUSE mount at `mountpoint`, lazily
+    /// decompressing each file's bytes as a reader touches them instead of requiring `install` to
+    /// have materialized the whole dataset to disk first. Returns a session handle that keeps the
+    /// mount alive; dropping it (or calling `.join()` on it) unmounts.
+    pub fn mount(
+        &self,
+        name: &types::Name,
+        mountpoint: &std::path::Path,
+    ) -> Result<fuser::BackgroundSession, MountError> {
+        let dataset = self
+            .datasets
+            .iter()
+            .find(|dataset| &dataset.name == name)
+            .ok_or_else(|| MountError::NotInstalled {
+                path_id: types::PathId(name.0.clone()),
+            })?;
+        let path_root = types::PathRoot::new(
+            std::sync::Arc::<std::path::PathBuf>::from(self.directory.clone()),
+            self.storage()?,
+        );
+        mount::mount(path_root, types::PathId(dataset.name.0.clone()), mountpoint)
     }
 }
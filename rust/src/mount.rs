@@ -0,0 +1,429 @@
+use crate::decode;
+use crate::json_index;
+use crate::types;
+use std::io::Read;
+
+// the FUSE convention: inode 1 is always the mount's root directory
+const ROOT_INODE: u64 = 1;
+
+// how long the kernel may cache an inode's attributes / a directory's listing before asking
+// again; the tree is read-only and content-addressed for the lifetime of a mount, so there is
+// nothing to invalidate in between
+const ATTR_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// how many files' decompressor state (and trailing output cache) are kept around at once, so
+// a handful of files being read concurrently (or re-opened back to back) don't each pay a
+// restart-from-zero cost; bounded so a directory walk over thousands of files does not pin open
+// file handles and buffers for all of them forever
+const OPEN_DECODER_CAPACITY: usize = 8;
+
+// how many trailing bytes of a file's decompressed output are kept buffered per open decoder, so
+// a read that overlaps the tail of the previous one is served from memory instead of restarting
+// decompression from the beginning
+const OUTPUT_CACHE_SIZE: usize = 1 << 20; // 1 MiB
+
+#[derive(Debug, thiserror::Error)]
+pub enum MountError {
+    #[error("mounting the filesystem failed")]
+    Mount(#[from] std::io::Error),
+
+    #[error("index error")]
+    Index(#[from] types::ActionError),
+
+    #[error("decompress error")]
+    Decompress(#[from] types::DecompressError),
+
+    #[error("{path_id:?} has no on-disk index; install or plan the dataset first")]
+    NotInstalled { path_id: types::PathId },
+}
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Directory,
+    File {
+        resource: std::sync::Arc<json_index::Resource>,
+    },
+}
+
+struct Inode {
+    parent: u64,
+    name: types::Name,
+    path_id: types::PathId,
+    entry: Entry,
+    // populated lazily: `None` until the directory's `-index.json` has been read at least once
+    children: Option<Vec<u64>>,
+}
+
+/// A decompressor parked `position` raw (decompressed) bytes into a file, plus the trailing bytes
+/// it has already produced. `hasher` stays `Some` until the decoder reaches the end of the file and
+/// the digest is checked: a read ahead of `position` decodes and hashes the bytes it skips, so the
+/// running digest still covers every byte in order, and a read behind `position` that misses the
+/// cache restarts both the decoder and the hasher from the start of the file.
+struct OpenDecoder {
+    reader: Box<dyn Read + Send>,
+    position: u64,
+    cache_start: u64,
+    cache: Vec<u8>,
+    hasher: Option<Box<dyn types::StreamingHasher + Send>>,
+    expected_hash: types::Hash,
+}
+
+/// A read-only FUSE view over an already-installed (or partially-installed) dataset directory.
+/// Directories are listed from their `-index.json` file exactly like `verify_directory` reads
+/// them; a file's `stat` size is its declared decompressed `resource.size`, and reading it drives
+/// a streaming `decode::Codec` reader to the requested offset rather than eagerly materializing
+/// the whole file to disk first, so a terabyte-scale dataset can be browsed while only paying
+/// decompression cost for the bytes actually touched.
+pub struct Filesystem {
+    path_root: types::PathRoot,
+    inodes: std::collections::HashMap<u64, Inode>,
+    next_inode: u64,
+    open_decoders: lru::LruCache<u64, OpenDecoder>,
+}
+
+impl Filesystem {
+    pub fn new(
+        path_root: types::PathRoot,
+        path_id: types::PathId,
+    ) -> Result<Filesystem, MountError> {
+        if !std::fs::metadata(path_root.join(&path_id.join(&types::Name("-index.json".to_owned()))))
+            .map(|metadata| metadata.file_type().is_file())
+            .unwrap_or(false)
+        {
+            return Err(MountError::NotInstalled { path_id });
+        }
+        let mut inodes = std::collections::HashMap::new();
+        inodes.insert(
+            ROOT_INODE,
+            Inode {
+                parent: ROOT_INODE,
+                name: types::Name(String::new()),
+                path_id,
+                entry: Entry::Directory,
+                children: None,
+            },
+        );
+        Ok(Filesystem {
+            path_root,
+            inodes,
+            next_inode: ROOT_INODE + 1,
+            open_decoders: lru::LruCache::new(
+                std::num::NonZeroUsize::new(OPEN_DECODER_CAPACITY).unwrap(),
+            ),
+        })
+    }
+
+    /// Reads `inode`'s `-index.json` the first time it is listed or descended into, inserting one
+    /// child `Inode` per sub-directory / file / other-file. A later call is a no-op: the tree is
+    /// read-only for the lifetime of a mount, so nothing on disk can invalidate it in between.
+    fn ensure_children(&mut self, inode: u64) -> Result<&[u64], MountError> {
+        if self.inodes.get(&inode).map(|node| node.children.is_some()) == Some(true) {
+            return Ok(self.inodes[&inode].children.as_deref().unwrap());
+        }
+        let path_id = self.inodes[&inode].path_id.clone();
+        let index_path = self
+            .path_root
+            .join(&path_id.join(&types::Name("-index.json".to_owned())));
+        let index = json_index::Index::from_bytes(&std::fs::read(&index_path)?)?;
+        let mut children = Vec::new();
+        for directory in &index.directories {
+            let child_inode = self.next_inode;
+            self.next_inode += 1;
+            self.inodes.insert(
+                child_inode,
+                Inode {
+                    parent: inode,
+                    name: directory.clone(),
+                    path_id: path_id.join(directory),
+                    entry: Entry::Directory,
+                    children: None,
+                },
+            );
+            children.push(child_inode);
+        }
+        for resource in index
+            .files
+            .into_iter()
+            .map(|file| file.resource)
+            .chain(index.other_files.into_iter().map(|other_file| other_file.resource))
+        {
+            let child_inode = self.next_inode;
+            self.next_inode += 1;
+            let name = resource.name.clone();
+            self.inodes.insert(
+                child_inode,
+                Inode {
+                    parent: inode,
+                    path_id: path_id.join(&name),
+                    name,
+                    entry: Entry::File {
+                        resource: std::sync::Arc::new(resource),
+                    },
+                    children: None,
+                },
+            );
+            children.push(child_inode);
+        }
+        let node = self.inodes.get_mut(&inode).unwrap();
+        node.children = Some(children);
+        Ok(node.children.as_deref().unwrap())
+    }
+
+    fn attr(&self, inode: u64) -> fuser::FileAttr {
+        let node = &self.inodes[&inode];
+        let (kind, perm, size) = match &node.entry {
+            Entry::Directory => (fuser::FileType::Directory, 0o555, 0),
+            Entry::File { resource } => (fuser::FileType::RegularFile, 0o444, resource.size),
+        };
+        let now = std::time::SystemTime::now();
+        fuser::FileAttr {
+            ino: inode,
+            size,
+            blocks: size.div_ceil(512),
+            atime: now,
+            mtime: now,
+            ctime: now,
+            crtime: now,
+            kind,
+            perm,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        }
+    }
+
+    /// Opens the compressed (or, for `NoneCompression`, the already-raw) on-disk variant of
+    /// `resource` from scratch, positioned at the start of its decompressed output.
+    fn open_from_start(
+        &self,
+        path_id: &types::PathId,
+        resource: &json_index::Resource,
+    ) -> Result<Box<dyn Read + Send>, MountError> {
+        let (compression, compression_properties) = resource.best_compression();
+        let source_path = match decode::Codec::from_compression(compression) {
+            Some(_) => self
+                .path_root
+                .join_with_suffix(path_id, &compression_properties.suffix.0),
+            None => self.path_root.join(path_id),
+        };
+        let file = std::fs::File::open(source_path)?;
+        Ok(match decode::Codec::from_compression(compression) {
+            Some(codec) => codec.reader(file)?,
+            None => Box::new(file),
+        })
+    }
+
+    /// Advances (or (re)creates) the `inode`'s parked decoder so that reading from `offset` is
+    /// possible, then copies up to `buffer.len()` raw bytes starting at `offset` into `buffer`,
+    /// returning the number of bytes actually copied. A call serves at most one cache slice or one
+    /// decoder read, so this can be fewer than `buffer.len()` before end of file as well as at it.
+    fn read_at(&mut self, inode: u64, offset: u64, buffer: &mut [u8]) -> Result<usize, MountError> {
+        let (path_id, resource) = match &self.inodes[&inode].entry {
+            Entry::File { resource } => (self.inodes[&inode].path_id.clone(), resource.clone()),
+            Entry::Directory => return Ok(0),
+        };
+        if offset >= resource.size {
+            return Ok(0);
+        }
+        if let Some(decoder) = self.open_decoders.get(&inode) {
+            let cache_end = decoder.cache_start + decoder.cache.len() as u64;
+            if offset >= decoder.cache_start && offset < cache_end {
+                let start = (offset - decoder.cache_start) as usize;
+                let length = buffer.len().min(decoder.cache.len() - start);
+                buffer[0..length].copy_from_slice(&decoder.cache[start..start + length]);
+                // the cached slice may fall short of `buffer`, since it only covers what was
+                // already produced; `Filesystem::read` loops on a non-zero, non-full return
+                // instead of assuming a short read here means EOF (the kernel's page-cache path
+                // does not re-issue for the remainder of a page on its own)
+                return Ok(length);
+            }
+        }
+        let needs_restart = match self.open_decoders.get(&inode) {
+            Some(decoder) => offset < decoder.position,
+            None => true,
+        };
+        if needs_restart {
+            let reader = self.open_from_start(&path_id, &resource)?;
+            self.open_decoders.put(
+                inode,
+                OpenDecoder {
+                    reader,
+                    position: 0,
+                    cache_start: 0,
+                    cache: Vec::new(),
+                    hasher: Some(resource.hash.algorithm.hasher()),
+                    expected_hash: resource.hash.clone(),
+                },
+            );
+        }
+        let decoder = self.open_decoders.get_mut(&inode).unwrap();
+        let mut discard = vec![0u8; OUTPUT_CACHE_SIZE.min(65536)];
+        while decoder.position < offset {
+            let to_read = discard.len().min((offset - decoder.position) as usize);
+            let count = decoder.reader.read(&mut discard[0..to_read])?;
+            if count == 0 {
+                break;
+            }
+            if let Some(hasher) = &mut decoder.hasher {
+                hasher.update(&discard[0..count]);
+            }
+            decoder.position += count as u64;
+        }
+        // bytes skipped above are not cached, so the cache restarts here to stay contiguous
+        if decoder.cache_start + decoder.cache.len() as u64 != decoder.position {
+            decoder.cache.clear();
+            decoder.cache_start = decoder.position;
+        }
+        let count = decoder.reader.read(buffer)?;
+        if let Some(hasher) = &mut decoder.hasher {
+            hasher.update(&buffer[0..count]);
+        }
+        decoder.position += count as u64;
+        if decoder.position >= resource.size {
+            if let Some(hasher) = decoder.hasher.take() {
+                let digest = hasher.finalize();
+                if digest != decoder.expected_hash.digest {
+                    log::warn!(
+                        "{path_id:?}: hash mismatch detected while serving it through the FUSE mount"
+                    );
+                }
+            }
+        }
+        decoder.cache.extend_from_slice(&buffer[0..count]);
+        if decoder.cache.len() > OUTPUT_CACHE_SIZE {
+            let drop_count = decoder.cache.len() - OUTPUT_CACHE_SIZE;
+            decoder.cache.drain(0..drop_count);
+            decoder.cache_start += drop_count as u64;
+        }
+        Ok(count)
+    }
+}
+
+impl fuser::Filesystem for Filesystem {
+    fn lookup(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        parent: u64,
+        name: &std::ffi::OsStr,
+        reply: fuser::ReplyEntry,
+    ) {
+        let children = match self.ensure_children(parent) {
+            Ok(children) => children.to_vec(),
+            Err(_) => return reply.error(libc::ENOENT),
+        };
+        match children
+            .into_iter()
+            .find(|child| Some(self.inodes[child].name.0.as_str()) == name.to_str())
+        {
+            Some(child) => reply.entry(&ATTR_TTL, &self.attr(child), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &fuser::Request<'_>, inode: u64, reply: fuser::ReplyAttr) {
+        match self.inodes.contains_key(&inode) {
+            true => reply.attr(&ATTR_TTL, &self.attr(inode)),
+            false => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: fuser::ReplyDirectory,
+    ) {
+        let parent = self.inodes[&inode].parent;
+        let mut entries = vec![
+            (inode, fuser::FileType::Directory, ".".to_owned()),
+            (parent, fuser::FileType::Directory, "..".to_owned()),
+        ];
+        match self.ensure_children(inode) {
+            Ok(children) => {
+                let children = children.to_vec();
+                for child in children {
+                    let kind = match &self.inodes[&child].entry {
+                        Entry::Directory => fuser::FileType::Directory,
+                        Entry::File { .. } => fuser::FileType::RegularFile,
+                    };
+                    entries.push((child, kind, self.inodes[&child].name.0.clone()));
+                }
+            }
+            Err(_) => return reply.error(libc::ENOENT),
+        }
+        for (index, (child_inode, kind, name)) in
+            entries.into_iter().enumerate().skip(offset as usize)
+        {
+            if reply.add(child_inode, (index + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn open(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        inode: u64,
+        _flags: i32,
+        reply: fuser::ReplyOpen,
+    ) {
+        match self.inodes.get(&inode) {
+            Some(node) => match &node.entry {
+                Entry::File { .. } => reply.opened(0, 0),
+                Entry::Directory => reply.error(libc::EISDIR),
+            },
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read(
+        &mut self,
+        _req: &fuser::Request<'_>,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: fuser::ReplyData,
+    ) {
+        let mut buffer = vec![0u8; size as usize];
+        let mut filled = 0usize;
+        // `read_at` only promises to fill `buffer` up to whatever one cache slice or decoder read
+        // happens to produce; with the page cache in play (`open` does not set
+        // `FOPEN_DIRECT_IO`) a short, non-EOF read here is read back by the kernel as a hole and
+        // zero-filled, so every short `read_at` result short of true EOF is retried until the
+        // buffer is full
+        while filled < buffer.len() {
+            match self.read_at(inode, offset as u64 + filled as u64, &mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(count) => filled += count,
+                Err(_) => return reply.error(libc::EIO),
+            }
+        }
+        reply.data(&buffer[0..filled]);
+    }
+}
+
+/// Spawns a background FUSE session exposing the already-installed dataset directory
+/// `path_root.join(&path_id)` read-only at `mountpoint`. Dropping (or explicitly calling
+/// `.join()` on) the returned session unmounts it.
+pub fn mount(
+    path_root: types::PathRoot,
+    path_id: types::PathId,
+    mountpoint: &std::path::Path,
+) -> Result<fuser::BackgroundSession, MountError> {
+    let filesystem = Filesystem::new(path_root, path_id)?;
+    let options = [
+        fuser::MountOption::RO,
+        fuser::MountOption::FSName("undr".to_owned()),
+    ];
+    Ok(fuser::spawn_mount2(filesystem, mountpoint, &options)?)
+}
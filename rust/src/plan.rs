@@ -0,0 +1,254 @@
+use crate::configuration;
+use crate::json_index;
+use crate::remote;
+use crate::types;
+use futures::future::FutureExt;
+
+/// The version of the `InstallPlan` schema, bumped whenever a field is added or removed so the
+/// frontend can tell an unfamiliar plan apart from one it knows how to render.
+pub const VERSION: u64 = 1;
+
+/// What `install` would do with a single resource, decided purely from local file presence (no
+/// hash re-check, to keep planning fast on large datasets).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    /// the file is not present locally and would be fetched from the remote server
+    Download,
+    /// the file is already present locally and `force` is not set, so nothing would happen
+    Skip,
+    /// the file is already present locally but `force` is set, so it would be re-downloaded
+    Overwrite,
+    /// the compressed variant is already present locally but the raw file is not, so only
+    /// decompression (no download) remains
+    Decode,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PlanCounts {
+    pub download: u64,
+    pub skip: u64,
+    pub overwrite: u64,
+    pub decode: u64,
+    pub download_bytes: u64,
+    pub decode_bytes: u64,
+}
+
+impl PlanCounts {
+    fn add(&mut self, action: PlanAction, download_bytes: u64, decode_bytes: u64) {
+        match action {
+            PlanAction::Download => {
+                self.download += 1;
+                self.download_bytes += download_bytes;
+            }
+            PlanAction::Skip => self.skip += 1,
+            PlanAction::Overwrite => {
+                self.overwrite += 1;
+                self.download_bytes += download_bytes;
+            }
+            PlanAction::Decode => {
+                self.decode += 1;
+                self.decode_bytes += decode_bytes;
+            }
+        }
+    }
+
+    pub(crate) fn extend(&mut self, other: &PlanCounts) {
+        self.download += other.download;
+        self.skip += other.skip;
+        self.overwrite += other.overwrite;
+        self.decode += other.decode;
+        self.download_bytes += other.download_bytes;
+        self.decode_bytes += other.decode_bytes;
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlanFile {
+    pub name: types::Name,
+    pub action: PlanAction,
+    pub download_bytes: u64,
+    pub decode_bytes: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlanDirectory {
+    pub path_id: types::PathId,
+    pub files: Vec<PlanFile>,
+    pub directories: Vec<PlanDirectory>,
+    pub counts: PlanCounts,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlanDataset {
+    pub name: types::Name,
+    pub root: PlanDirectory,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InstallPlan {
+    pub version: u64,
+    pub datasets: Vec<PlanDataset>,
+    pub total: PlanCounts,
+}
+
+fn classify(
+    path_root: &types::PathRoot,
+    resource_path_id: &types::PathId,
+    resource: &json_index::Resource,
+    force: types::Force,
+    mode: configuration::InstallableMode,
+) -> PlanFile {
+    let (_, compression_properties) = resource.best_compression();
+    // `install_directory` keeps the compressed variant on disk for `Local` datasets (the
+    // suffixed path) and decompresses to the bare path for `Raw` ones, so the "already
+    // installed" check must look in the same place `install` would have written to.
+    let final_path = if mode == configuration::InstallableMode::Raw {
+        path_root.join(resource_path_id)
+    } else {
+        path_root.join_with_suffix(resource_path_id, &compression_properties.suffix.0)
+    };
+    let action = match std::fs::metadata(&final_path) {
+        Ok(metadata) if metadata.file_type().is_file() => {
+            if force.0 {
+                PlanAction::Overwrite
+            } else {
+                PlanAction::Skip
+            }
+        }
+        _ => {
+            if !force.0 && mode == configuration::InstallableMode::Raw {
+                match std::fs::metadata(
+                    path_root.join_with_suffix(resource_path_id, &compression_properties.suffix.0),
+                ) {
+                    Ok(metadata) if metadata.file_type().is_file() => PlanAction::Decode,
+                    _ => PlanAction::Download,
+                }
+            } else {
+                PlanAction::Download
+            }
+        }
+    };
+    PlanFile {
+        name: resource.name.clone(),
+        action,
+        download_bytes: compression_properties.size,
+        decode_bytes: resource.size,
+    }
+}
+
+/// Walks a dataset directory exactly like `install_directory` does to reach each resource's
+/// index entry (downloading only the (tiny) `-index.json` files along the way, never the data
+/// files themselves), and classifies every resource into a `PlanAction` instead of acting on it.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_directory(
+    running: types::RunControl,
+    server: remote::Server,
+    sender: tokio::sync::mpsc::UnboundedSender<types::Message>,
+    path_root: types::PathRoot,
+    path_id: types::PathId,
+    force: types::Force,
+    mode: configuration::InstallableMode,
+    file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+    download_index_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
+) -> std::pin::Pin<
+    std::boxed::Box<dyn futures::future::Future<Output = Result<PlanDirectory, types::ActionError>> + Send>,
+> {
+    async move {
+        std::fs::create_dir_all(path_root.join(&path_id))?;
+        let index_path_id = path_id.join(&types::Name("-index.json".to_owned()));
+        server
+            .download_file(
+                &sender,
+                path_root.clone(),
+                &index_path_id,
+                force,
+                None,
+                None,
+                &types::Name(String::new()),
+                None,
+                download_index_semaphore.clone(),
+                file_semaphore.clone(),
+            )
+            .await?;
+        let index: json_index::Index = {
+            let content = {
+                let _permit = file_semaphore.acquire().await?;
+                std::fs::read(path_root.join(&index_path_id))
+                    .map_err(|_| types::ActionError::Read(path_root.join(&index_path_id)))?
+            };
+            json_index::Index::from_bytes(&content)?
+        };
+        sender
+            .send(types::Message::IndexLoaded {
+                path_id: path_id.clone(),
+                children: index.directories.len(),
+            })
+            .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+        let mut join_set = tokio::task::JoinSet::new();
+        for directory in &index.directories {
+            let running = running.clone();
+            let server = server.clone();
+            let sender = sender.clone();
+            let path_root = path_root.clone();
+            let path_id = path_id.join(directory);
+            let file_semaphore = file_semaphore.clone();
+            let download_index_semaphore = download_index_semaphore.clone();
+            join_set.spawn(plan_directory(
+                running,
+                server,
+                sender,
+                path_root,
+                path_id,
+                force,
+                mode,
+                file_semaphore,
+                download_index_semaphore,
+            ));
+        }
+        let mut directories = Vec::new();
+        while let Some(task) = join_set.join_next().await {
+            match task {
+                Ok(result) => directories.push(result?),
+                Err(error) => return Err(types::ActionError::Join(error)),
+            }
+        }
+        let mut counts = PlanCounts::default();
+        let files = if mode == configuration::InstallableMode::Remote {
+            Vec::new()
+        } else {
+            let mut files = Vec::new();
+            for resource in index
+                .files
+                .iter()
+                .map(|file| &file.resource)
+                .chain(index.other_files.iter().map(|other_file| &other_file.resource))
+            {
+                if running.is_stopped() {
+                    break;
+                }
+                running.wait_if_paused().await;
+                let plan_file = classify(
+                    &path_root,
+                    &path_id.join(&resource.name),
+                    resource,
+                    force,
+                    mode,
+                );
+                counts.add(plan_file.action, plan_file.download_bytes, plan_file.decode_bytes);
+                files.push(plan_file);
+            }
+            files
+        };
+        for directory in &directories {
+            counts.extend(&directory.counts);
+        }
+        Ok(PlanDirectory {
+            path_id,
+            files,
+            directories,
+            counts,
+        })
+    }
+    .boxed()
+}
@@ -1,13 +1,159 @@
 use crate::constants;
+use crate::decode;
+use crate::json_index;
+use crate::store;
 use crate::types;
-use sha3::Digest;
+use std::io::Seek;
 use std::io::Write;
 
+/// Relays an async byte stream while accumulating how many bytes it has yielded into `counter`.
+/// Placed between the transport's raw stream and a `decode::Codec` decoder so that progress can
+/// still be reported against the compressed bytes actually received over the wire, even though
+/// the decoder is the thing `drain_with_retries` ends up reading from.
+struct CountingReader {
+    inner: std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>,
+    counter: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl tokio::io::AsyncRead for CountingReader {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        context: &mut std::task::Context<'_>,
+        buffer: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled_before = buffer.filled().len();
+        let result = this.inner.as_mut().poll_read(context, buffer);
+        if result.is_ready() {
+            this.counter.fetch_add(
+                (buffer.filled().len() - filled_before) as u64,
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+        result
+    }
+}
+
+fn read_bitfield(path: &std::path::Path, piece_count: usize) -> Option<Vec<bool>> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.len() != (piece_count + 7) / 8 {
+        return None;
+    }
+    Some(
+        (0..piece_count)
+            .map(|index| (bytes[index / 8] >> (index % 8)) & 1 == 1)
+            .collect(),
+    )
+}
+
+fn write_bitfield(path: &std::path::Path, completed: &[bool]) -> std::io::Result<()> {
+    let mut bytes = vec![0u8; (completed.len() + 7) / 8];
+    for (index, done) in completed.iter().enumerate() {
+        if *done {
+            bytes[index / 8] |= 1 << (index % 8);
+        }
+    }
+    std::fs::write(path, bytes)
+}
+
+/// An error surfacing through a `Transport`'s byte stream is treated as retryable unless it
+/// clearly isn't a connection/timeout problem (e.g. a non-`reqwest::Error` source, meaning a
+/// non-HTTP transport, is always retried since those backends don't distinguish error causes).
+fn retryable(error: &std::io::Error) -> bool {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+    {
+        Some(reqwest_error) => {
+            reqwest_error.is_connect()
+                || reqwest_error.is_timeout()
+                || reqwest_error.is_body()
+                || reqwest_error.is_request()
+        }
+        None => true,
+    }
+}
+
+/// A `reqwest::Error` surfacing from opening a `Transport` (before any bytes have streamed) is
+/// retryable if it is a connection/timeout problem, or (now that `HttpTransport::open` calls
+/// `error_for_status`) a 5xx / 429 response.
+fn retryable_request_error(error: &reqwest::Error) -> bool {
+    error.is_connect()
+        || error.is_timeout()
+        || error
+            .status()
+            .map(types::RetryPolicy::is_retryable_status)
+            .unwrap_or(false)
+}
+
+/// A shared token bucket that caps aggregate download throughput across every `download_file`
+/// task running against the same `Server`. Tokens (bytes) refill continuously at `rate` bytes/sec
+/// up to the `burst` ceiling; `acquire` blocks asynchronously until enough tokens are available,
+/// so one slow limiter does not spin the executor while waiting.
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    state: tokio::sync::Mutex<RateLimiterState>,
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second as f64;
+        RateLimiter {
+            rate,
+            burst: rate * constants::RATE_LIMIT_BURST_SECONDS,
+            state: tokio::sync::Mutex::new(RateLimiterState {
+                tokens: rate * constants::RATE_LIMIT_BURST_SECONDS,
+                last_refill: std::time::Instant::now(),
+            }),
+        }
+    }
+
+    async fn acquire(&self, bytes: u64) {
+        let mut bytes_needed = bytes as f64;
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = std::time::Instant::now();
+                state.tokens = (state.tokens
+                    + now.duration_since(state.last_refill).as_secs_f64() * self.rate)
+                    .min(self.burst);
+                state.last_refill = now;
+                if state.tokens >= bytes_needed {
+                    state.tokens -= bytes_needed;
+                    None
+                } else {
+                    bytes_needed -= state.tokens;
+                    state.tokens = 0.0;
+                    Some(std::time::Duration::from_secs_f64(bytes_needed / self.rate))
+                }
+            };
+            match wait {
+                None => return,
+                Some(duration) => tokio::time::sleep(duration).await,
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Server {
     client: reqwest::Client,
+    transport: std::sync::Arc<dyn crate::transport::Transport>,
     url: std::sync::Arc<str>,
     url_ends_with_separator: bool,
+    // mirrors the scheme branch in `transport::from_url`: `file://`/`sftp://` get their own
+    // `Transport`, anything else (including an unrecognized scheme) falls back to HTTP
+    is_http: bool,
+    retry_policy: types::RetryPolicy,
+    rate_limiter: Option<std::sync::Arc<RateLimiter>>,
 }
 
 pub enum DownloadState<Context> {
@@ -18,22 +164,39 @@ pub enum DownloadState<Context> {
 
 struct DownloadFileContext {
     file: std::fs::File,
-    hasher: Option<sha3::Sha3_224>,
+    hasher: Option<Box<dyn types::StreamingHasher + Send>>,
     size: Option<u64>,
     download_permit: tokio::sync::OwnedSemaphorePermit,
     file_permit: tokio::sync::OwnedSemaphorePermit,
 }
 
 impl Server {
-    pub fn new(url: &url::Url, timeout: &Option<f64>) -> Result<Self, reqwest::Error> {
+    pub fn new(
+        url: &url::Url,
+        timeout: &Option<f64>,
+        max_retries: &Option<u32>,
+        max_bytes_per_second: &Option<u64>,
+        retry_policy: &types::RetryPolicy,
+    ) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(std::time::Duration::from_secs_f64(
+                timeout.unwrap_or(constants::DEFAULT_TIMEOUT),
+            ))
+            .build()?;
         Ok(Server {
-            client: reqwest::Client::builder()
-                .connect_timeout(std::time::Duration::from_secs_f64(
-                    timeout.unwrap_or(constants::DEFAULT_TIMEOUT),
-                ))
-                .build()?,
+            transport: crate::transport::from_url(url, client.clone()),
+            client,
             url: std::sync::Arc::<str>::from(url.as_str()),
             url_ends_with_separator: url.as_str().ends_with('/'),
+            is_http: !matches!(url.scheme(), "file" | "sftp"),
+            // a dataset's own `max_retries` (from its TOML config) overrides the attempt count of
+            // the caller's `retry_policy`, but the backoff bounds always come from `retry_policy`
+            retry_policy: types::RetryPolicy {
+                max_attempts: max_retries.unwrap_or(retry_policy.max_attempts),
+                ..*retry_policy
+            },
+            rate_limiter: max_bytes_per_second
+                .map(|bytes_per_second| std::sync::Arc::new(RateLimiter::new(bytes_per_second))),
         })
     }
 
@@ -63,13 +226,60 @@ impl Server {
         }
     }
 
+    /// Whether this server was constructed from an `http(s)://` URL (or one with an unrecognized
+    /// scheme, which `transport::from_url` also falls back to HTTP for). `download_file_pieces`
+    /// only makes sense against a server that can answer byte-range `GET` requests directly, so
+    /// `install_directory::install_directory` checks this before picking piece-hashed downloads over the
+    /// `Transport`-backed whole-file path.
+    pub fn is_http(&self) -> bool {
+        self.is_http
+    }
+
+    /// Opens `url` like `Transport::open`, but retries a connection/timeout error or a 5xx / 429
+    /// response (see `retryable_request_error`) with `self.retry_policy`'s backoff instead of
+    /// surfacing it immediately; this only covers the request that precedes any byte streaming,
+    /// the same transient failures `drain_with_retries` retries once bytes are already flowing.
+    async fn open_with_retries(
+        &self,
+        url: &str,
+        offset: u64,
+    ) -> Result<
+        (
+            std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>,
+            bool,
+        ),
+        types::DownloadError,
+    > {
+        let mut attempt = 0;
+        loop {
+            match self.transport.open(url, offset).await {
+                Ok(result) => return Ok(result),
+                Err(types::DownloadError::Connection(error))
+                    if attempt < self.retry_policy.max_attempts
+                        && retryable_request_error(&error) =>
+                {
+                    tokio::time::sleep(self.retry_policy.delay(attempt)).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
     pub async fn start_download<OnBegin, OnBeginOutput, OnRangeFailed, Context>(
         &self,
         path_id: &types::PathId,
         suffix: &types::Name,
         on_begin: OnBegin,
         on_range_failed: OnRangeFailed,
-    ) -> Result<Option<(reqwest::Response, Context)>, types::DownloadError>
+    ) -> Result<
+        Option<(
+            std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>,
+            Context,
+        )>,
+        types::DownloadError,
+    >
     where
         OnBegin: Fn() -> OnBeginOutput,
         OnBeginOutput:
@@ -78,36 +288,186 @@ impl Server {
     {
         match on_begin().await? {
             DownloadState::Complete() => Ok(None),
-            DownloadState::NotStarted(context) => Ok(Some((
-                self.client
-                    .get(&self.url_from_path_id_and_suffix(path_id, suffix))
-                    .send()
-                    .await?,
-                context,
-            ))),
+            DownloadState::NotStarted(context) => {
+                let (stream, _) = self
+                    .open_with_retries(&self.url_from_path_id_and_suffix(path_id, suffix), 0)
+                    .await?;
+                Ok(Some((stream, context)))
+            }
             DownloadState::Partial { skip, context } => {
-                let response = self
-                    .client
-                    .get(&self.url_from_path_id_and_suffix(path_id, suffix))
-                    .header(reqwest::header::RANGE, &format!("bytes={skip}-"))
-                    .send()
+                let (stream, resumed) = self
+                    .open_with_retries(&self.url_from_path_id_and_suffix(path_id, suffix), skip)
                     .await?;
-                if response.status() == 206 {
-                    Ok(Some((response, context)))
+                if resumed {
+                    Ok(Some((stream, context)))
                 } else {
                     let context = on_range_failed(skip, context)?;
-                    Ok(Some((
-                        self.client
-                            .get(&self.url_from_path_id_and_suffix(path_id, suffix))
-                            .send()
-                            .await?,
-                        context,
-                    )))
+                    let (stream, _) = self
+                        .open_with_retries(&self.url_from_path_id_and_suffix(path_id, suffix), 0)
+                        .await?;
+                    Ok(Some((stream, context)))
+                }
+            }
+        }
+    }
+
+    /// Feeds `response`'s body chunks into `context` until the stream ends, transparently
+    /// resuming on a transient connection/timeout error: the request is re-issued with
+    /// `Range: bytes={written}-`, or (if the server answers without partial-content support)
+    /// the destination file and hasher/size accumulators are reset and the download restarts
+    /// from byte 0. Gives up once `self.retry_policy.max_attempts` retries have failed over the
+    /// whole download; the count is not reset when a retry succeeds in reading more bytes.
+    ///
+    /// When `codec` is set, `response` is piped through that decoder so `context.hasher` and
+    /// `context.size` are updated from the *decompressed* bytes while `context.file` ends up
+    /// holding decompressed content; a decoder's internal state is never persisted to disk, so a
+    /// compressed stream is never Range-resumed — any retry re-requests the compressed resource
+    /// from byte 0, truncating `context.file` and resetting the hasher/size first. Progress is
+    /// still reported against the compressed bytes received, via a counter spliced in ahead of
+    /// the decoder, so throughput stays meaningful.
+    async fn drain_with_retries<Message>(
+        &self,
+        sender: &tokio::sync::mpsc::UnboundedSender<Message>,
+        path_id: &types::PathId,
+        suffix: &types::Name,
+        expected_hash: &Option<types::Hash>,
+        codec: Option<decode::Codec>,
+        mut stream: std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>,
+        mut context: DownloadFileContext,
+    ) -> Result<DownloadFileContext, types::DownloadError>
+    where
+        Message: std::convert::From<types::RemoteProgress>,
+        Message: std::fmt::Debug,
+    {
+        use tokio::io::AsyncReadExt;
+        let mut attempt = 0;
+        let mut progress_size = 0;
+        let mut buffer = [0u8; constants::DECOMPRESS_CHUNK_SIZE];
+        let resumable = codec.is_none();
+        let compressed_counter =
+            codec.map(|_| std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0)));
+        let mut reported_compressed_bytes = 0u64;
+        if let (Some(codec), Some(counter)) = (codec, &compressed_counter) {
+            stream = codec.wrap(Box::pin(CountingReader {
+                inner: stream,
+                counter: counter.clone(),
+            }));
+        }
+        loop {
+            match stream.read(&mut buffer).await {
+                Ok(0) => {
+                    if let Some(counter) = &compressed_counter {
+                        let total = counter.load(std::sync::atomic::Ordering::Relaxed);
+                        progress_size += (total - reported_compressed_bytes) as i64;
+                        reported_compressed_bytes = total;
+                    }
+                    if progress_size > 0 {
+                        sender
+                            .send(
+                                types::RemoteProgress {
+                                    path_id: path_id.clone(),
+                                    initial_bytes: 0,
+                                    current_bytes: progress_size,
+                                    final_bytes: progress_size,
+                                    complete: false,
+                                }
+                                .into(),
+                            )
+                            .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+                    }
+                    return Ok(context);
+                }
+                Ok(count) => {
+                    let chunk = &buffer[0..count];
+                    if let Some(rate_limiter) = &self.rate_limiter {
+                        rate_limiter.acquire(chunk.len() as u64).await;
+                    }
+                    context.file.write_all(chunk)?;
+                    if let Some(hasher) = context.hasher.as_mut() {
+                        hasher.update(chunk);
+                    }
+                    if let Some(size) = context.size.as_mut() {
+                        *size += count as u64;
+                    }
+                    match &compressed_counter {
+                        Some(counter) => {
+                            let total = counter.load(std::sync::atomic::Ordering::Relaxed);
+                            progress_size += (total - reported_compressed_bytes) as i64;
+                            reported_compressed_bytes = total;
+                        }
+                        None => progress_size += count as i64,
+                    }
+                    if progress_size >= constants::PROGRESS_SIZE {
+                        sender
+                            .send(
+                                types::RemoteProgress {
+                                    path_id: path_id.clone(),
+                                    initial_bytes: 0,
+                                    current_bytes: progress_size,
+                                    final_bytes: progress_size,
+                                    complete: false,
+                                }
+                                .into(),
+                            )
+                            .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+                        progress_size = 0;
+                    }
+                }
+                Err(error) => {
+                    if !retryable(&error) {
+                        return Err(types::DownloadError::File(error));
+                    }
+                    if attempt >= self.retry_policy.max_attempts {
+                        return Err(types::DownloadError::RetriesExhausted {
+                            path_id: path_id.clone(),
+                        });
+                    }
+                    tokio::time::sleep(self.retry_policy.delay(attempt)).await;
+                    attempt += 1;
+                    let written = context.file.stream_position()?;
+                    let (retried_stream, resumed) = self
+                        .open_with_retries(
+                            &self.url_from_path_id_and_suffix(path_id, suffix),
+                            if resumable { written } else { 0 },
+                        )
+                        .await?;
+                    if resumed && resumable {
+                        stream = retried_stream;
+                    } else {
+                        context.file.set_len(0)?;
+                        context.file.seek(std::io::SeekFrom::Start(0))?;
+                        context.hasher = expected_hash.as_ref().map(|hash| hash.algorithm.hasher());
+                        context.size = context.size.map(|_| 0);
+                        progress_size = 0;
+                        stream = match (codec, &compressed_counter) {
+                            (Some(codec), Some(counter)) => {
+                                counter.store(0, std::sync::atomic::Ordering::Relaxed);
+                                reported_compressed_bytes = 0;
+                                codec.wrap(Box::pin(CountingReader {
+                                    inner: retried_stream,
+                                    counter: counter.clone(),
+                                }))
+                            }
+                            _ => retried_stream,
+                        };
+                    }
                 }
             }
         }
     }
 
+    /// Downloads `suffix` into `file_path`. When `codec` is set, `suffix` names a compressed
+    /// remote variant that is decoded in-flight (see `drain_with_retries`), so `expected_size` /
+    /// `expected_hash` must be the *decompressed* resource's size/hash and `file_path` is the
+    /// plain (uncompressed) destination rather than `path_id` + `suffix`; a partially-written
+    /// `.download` file left over from an earlier attempt is never reused in that case, since a
+    /// decoder's state cannot be reconstructed from bytes already on disk.
+    ///
+    /// A leftover `.download` partial for an uncompressed resource is only ever reused when
+    /// `expected_hash` is set, i.e. for data files rather than `-index.json` (which carries no
+    /// hash and can go stale, so it is always re-fetched from byte 0). When such a partial is
+    /// already `expected_size` bytes long it is hashed and verified directly instead of being
+    /// resumed, since a follow-up Range request from that offset would only ever come back empty.
     #[allow(clippy::too_many_arguments)]
     pub async fn download_file<Message>(
         &self,
@@ -118,7 +478,8 @@ impl Server {
         expected_size: Option<u64>,
         expected_hash: Option<types::Hash>,
         suffix: &types::Name,
-        download_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+        codec: Option<decode::Codec>,
+        download_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
         file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
     ) -> Result<(), types::DownloadError>
     where
@@ -127,25 +488,30 @@ impl Server {
     {
         let download_path =
             path_root.join_with_suffixes(path_id, &suffix.0, constants::DOWNLOAD_SUFFIX);
-        let file_path = path_root.join_with_suffix(path_id, &suffix.0);
-        if let Some((mut response, mut context)) = self
+        let file_path = match codec {
+            Some(_) => path_root.join(path_id),
+            None => path_root.join_with_suffix(path_id, &suffix.0),
+        };
+        if let Some((response, mut context)) = self
             .start_download(
                 path_id,
                 suffix,
                 || {
                     let path_id = path_id.clone();
+                    let path_root = path_root.clone();
                     let download_path = download_path.clone();
                     let file_path = file_path.clone();
                     let expected_hash = expected_hash.clone();
                     let file_semaphore = file_semaphore.clone();
                     let download_semaphore = download_semaphore.clone();
+                    let codec = codec;
                     async move {
                         if force.0 {
                             let download_permit = download_semaphore.acquire_owned().await?;
                             let file_permit = file_semaphore.acquire_many_owned(2).await?;
                             Ok(DownloadState::NotStarted(DownloadFileContext {
                                 file: std::fs::File::create(&download_path)?,
-                                hasher: expected_hash.as_ref().map(|_| sha3::Sha3_224::new()),
+                                hasher: expected_hash.as_ref().map(|hash| hash.algorithm.hasher()),
                                 size: expected_size.map(|_| 0),
                                 download_permit,
                                 file_permit,
@@ -168,18 +534,80 @@ impl Server {
                                         .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
                                     Ok(DownloadState::Complete())
                                 }
+                                // Resuming is only attempted for data files (those carrying an
+                                // `expected_hash`): index files have no hash to seed a resumed
+                                // hasher from and go stale, so a leftover `.download` partial for
+                                // one is always discarded and re-fetched from byte 0 below.
                                 _ => match std::fs::metadata(&download_path) {
-                                    Ok(metadata) if metadata.file_type().is_file() => {
-                                        let download_permit = download_semaphore.acquire_owned().await?;
-                                        let file_permit = file_semaphore.acquire_many_owned(2).await?;
-                                        let hasher = expected_hash
-                                            .as_ref()
-                                            .map(|_| {
-                                                types::Hash::hasher_from_reader(
-                                                    std::fs::File::open(&download_path)?,
-                                                )
-                                            })
-                                            .transpose()?;
+                                    Ok(metadata)
+                                        if metadata.file_type().is_file()
+                                            && codec.is_none()
+                                            && expected_hash.is_some() =>
+                                    {
+                                        let expected_hash =
+                                            expected_hash.as_ref().expect("checked above");
+                                        if expected_size == Some(metadata.len()) {
+                                            // the partial already holds every byte the resource
+                                            // should have; a Range request from this offset would
+                                            // just get `416 Range Not Satisfiable` back, so verify
+                                            // what's on disk directly instead of reopening a
+                                            // connection.
+                                            let digest = types::Hash::hasher_from_reader(
+                                                expected_hash.algorithm,
+                                                std::fs::File::open(&download_path)?,
+                                            )?
+                                            .finalize();
+                                            if digest == expected_hash.digest {
+                                                let _file_permit =
+                                                    file_semaphore.acquire().await?;
+                                                std::fs::rename(&download_path, &file_path)?;
+                                                let _ = store::insert_file(
+                                                    &path_root.chunk_store_root(),
+                                                    expected_hash,
+                                                    &file_path,
+                                                );
+                                                let size = metadata.len() as i64;
+                                                sender
+                                                    .send(
+                                                        types::RemoteProgress {
+                                                            path_id: path_id.clone(),
+                                                            initial_bytes: size,
+                                                            current_bytes: size,
+                                                            final_bytes: size,
+                                                            complete: true,
+                                                        }
+                                                        .into(),
+                                                    )
+                                                    .map_err(|_| {
+                                                        types::DownloadError::Send(path_id.clone())
+                                                    })?;
+                                                return Ok(DownloadState::Complete());
+                                            }
+                                            // the partial is the right size but corrupt: discard
+                                            // it and fall back to a full re-download once.
+                                            let _ = std::fs::remove_file(&download_path);
+                                            let download_permit =
+                                                download_semaphore.acquire_owned().await?;
+                                            let file_permit =
+                                                file_semaphore.acquire_many_owned(2).await?;
+                                            return Ok(DownloadState::NotStarted(
+                                                DownloadFileContext {
+                                                    file: std::fs::File::create(&download_path)?,
+                                                    hasher: Some(expected_hash.algorithm.hasher()),
+                                                    size: expected_size.map(|_| 0),
+                                                    download_permit,
+                                                    file_permit,
+                                                },
+                                            ));
+                                        }
+                                        let download_permit =
+                                            download_semaphore.acquire_owned().await?;
+                                        let file_permit =
+                                            file_semaphore.acquire_many_owned(2).await?;
+                                        let hasher = Some(types::Hash::hasher_from_reader(
+                                            expected_hash.algorithm,
+                                            std::fs::File::open(&download_path)?,
+                                        )?);
                                         Ok(DownloadState::Partial {
                                             skip: metadata.len(),
                                             context: DownloadFileContext {
@@ -194,13 +622,47 @@ impl Server {
                                         })
                                     }
                                     _ => {
-                                        let download_permit = download_semaphore.acquire_owned().await?;
-                                        let file_permit = file_semaphore.acquire_many_owned(2).await?;
+                                        if let Some(expected_hash) = expected_hash.as_ref() {
+                                            let _file_permit = file_semaphore.acquire().await?;
+                                            if store::link_file(
+                                                &path_root.chunk_store_root(),
+                                                expected_hash,
+                                                &file_path,
+                                            )? {
+                                                let size = expected_size
+                                                    .or_else(|| {
+                                                        std::fs::metadata(&file_path)
+                                                            .ok()
+                                                            .map(|metadata| metadata.len())
+                                                    })
+                                                    .unwrap_or(0)
+                                                    as i64;
+                                                sender
+                                                    .send(
+                                                        types::RemoteProgress {
+                                                            path_id: path_id.clone(),
+                                                            initial_bytes: size,
+                                                            current_bytes: size,
+                                                            final_bytes: size,
+                                                            complete: true,
+                                                        }
+                                                        .into(),
+                                                    )
+                                                    .map_err(|_| {
+                                                        types::DownloadError::Send(path_id.clone())
+                                                    })?;
+                                                return Ok(DownloadState::Complete());
+                                            }
+                                        }
+                                        let download_permit =
+                                            download_semaphore.acquire_owned().await?;
+                                        let file_permit =
+                                            file_semaphore.acquire_many_owned(2).await?;
                                         Ok(DownloadState::NotStarted(DownloadFileContext {
                                             file: std::fs::File::create(&download_path)?,
                                             hasher: expected_hash
                                                 .as_ref()
-                                                .map(|_| sha3::Sha3_224::new()),
+                                                .map(|hash| hash.algorithm.hasher()),
                                             size: expected_size.map(|_| 0),
                                             download_permit,
                                             file_permit,
@@ -228,7 +690,7 @@ impl Server {
                     drop(context.file);
                     Ok(DownloadFileContext {
                         file: std::fs::File::create(&download_path)?,
-                        hasher: expected_hash.as_ref().map(|_| sha3::Sha3_224::new()),
+                        hasher: expected_hash.as_ref().map(|hash| hash.algorithm.hasher()),
                         size: expected_size.map(|_| 0),
                         file_permit: context.file_permit,
                         download_permit: context.download_permit,
@@ -237,58 +699,34 @@ impl Server {
             )
             .await?
         {
-            let mut progress_size = 0;
-            while let Some(chunk) = response.chunk().await? {
-                context.file.write_all(&chunk)?;
-                if let Some(hasher) = context.hasher.as_mut() {
-                    hasher.update(&chunk);
-                }
-                if let Some(size) = context.size.as_mut() {
-                    *size += chunk.len() as u64;
-                }
-                progress_size += chunk.len() as i64;
-                if progress_size >= constants::PROGRESS_SIZE {
-                    sender
-                        .send(
-                            types::RemoteProgress {
-                                path_id: path_id.clone(),
-                                initial_bytes: 0,
-                                current_bytes: progress_size,
-                                final_bytes: progress_size,
-                                complete: false,
-                            }
-                            .into(),
-                        )
-                        .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
-                    progress_size = 0;
-                }
-            }
-            if progress_size > 0 {
-                sender
-                    .send(
-                        types::RemoteProgress {
-                            path_id: path_id.clone(),
-                            initial_bytes: 0,
-                            current_bytes: progress_size,
-                            final_bytes: progress_size,
-                            complete: false,
-                        }
-                        .into(),
-                    )
-                    .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
-            }
+            context = self
+                .drain_with_retries(
+                    sender,
+                    path_id,
+                    suffix,
+                    &expected_hash,
+                    codec,
+                    response,
+                    context,
+                )
+                .await?;
             drop(context.file);
             drop(context.file_permit);
             drop(context.download_permit);
+            let mut verified_hash = None;
             if let (Some(hasher), Some(expected_hash)) = (context.hasher, expected_hash) {
-                let hash = hasher.finalize();
-                if hash != expected_hash.0 {
+                let digest = hasher.finalize();
+                if digest != expected_hash.digest {
                     return Err(types::DownloadError::Hash {
                         path_id: path_id.clone(),
+                        downloaded: types::Hash {
+                            algorithm: expected_hash.algorithm,
+                            digest,
+                        },
                         expected: expected_hash,
-                        downloaded: types::Hash(hash),
                     });
                 }
+                verified_hash = Some(expected_hash);
             }
             if let (Some(size), Some(expected_size)) = (context.size, expected_size) {
                 if size != expected_size {
@@ -300,6 +738,10 @@ impl Server {
                 }
             }
             std::fs::rename(&download_path, &file_path)?;
+            if let Some(expected_hash) = &verified_hash {
+                let _ =
+                    store::insert_file(&path_root.chunk_store_root(), expected_hash, &file_path);
+            }
             sender
                 .send(
                     types::RemoteProgress {
@@ -315,4 +757,223 @@ impl Server {
         }
         Ok(())
     }
+
+    /// Downloads a resource whose compression variant carries piece hashes, fetching and
+    /// verifying each missing piece independently (via HTTP `Range` requests) so that an
+    /// interrupted download only has to re-fetch the pieces that never landed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_file_pieces<Message>(
+        &self,
+        sender: &tokio::sync::mpsc::UnboundedSender<Message>,
+        path_root: types::PathRoot,
+        path_id: &types::PathId,
+        force: types::Force,
+        total_size: u64,
+        expected_hash: &types::Hash,
+        suffix: &types::Name,
+        pieces: &json_index::Pieces,
+        download_semaphore: std::sync::Arc<types::AdaptiveSemaphore>,
+        file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+    ) -> Result<(), types::DownloadError>
+    where
+        Message: std::convert::From<types::RemoteProgress>,
+        Message: std::fmt::Debug,
+    {
+        let download_path =
+            path_root.join_with_suffixes(path_id, &suffix.0, constants::DOWNLOAD_SUFFIX);
+        let file_path = path_root.join_with_suffix(path_id, &suffix.0);
+        let bitfield_path = std::path::PathBuf::from(format!(
+            "{}{}",
+            download_path.to_string_lossy(),
+            constants::BITFIELD_SUFFIX
+        ));
+        if !force.0 {
+            if let Ok(metadata) = std::fs::metadata(&file_path) {
+                if metadata.file_type().is_file() {
+                    let size = total_size as i64;
+                    sender
+                        .send(
+                            types::RemoteProgress {
+                                path_id: path_id.clone(),
+                                initial_bytes: size,
+                                current_bytes: size,
+                                final_bytes: size,
+                                complete: true,
+                            }
+                            .into(),
+                        )
+                        .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+                    return Ok(());
+                }
+            }
+        }
+        let piece_count = pieces.hashes.len();
+        let piece_range = |index: usize| -> (u64, u64) {
+            let start = index as u64 * pieces.length;
+            let end = std::cmp::min(start + pieces.length, total_size);
+            (start, end)
+        };
+        let completed = if force.0 {
+            vec![false; piece_count]
+        } else {
+            read_bitfield(&bitfield_path, piece_count).unwrap_or_else(|| vec![false; piece_count])
+        };
+        {
+            let _file_permit = file_semaphore.acquire().await?;
+            let file = std::fs::File::options()
+                .create(true)
+                .write(true)
+                .open(&download_path)?;
+            file.set_len(total_size)?;
+        }
+        let initial_bytes: i64 = completed
+            .iter()
+            .enumerate()
+            .filter(|(_, done)| **done)
+            .map(|(index, _)| {
+                let (start, end) = piece_range(index);
+                (end - start) as i64
+            })
+            .sum();
+        sender
+            .send(
+                types::RemoteProgress {
+                    path_id: path_id.clone(),
+                    initial_bytes,
+                    current_bytes: initial_bytes,
+                    final_bytes: total_size as i64,
+                    complete: false,
+                }
+                .into(),
+            )
+            .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+        let bitfield_path = std::sync::Arc::new(bitfield_path);
+        let completed_mutex = std::sync::Arc::new(tokio::sync::Mutex::new(completed.clone()));
+        let pending_pieces = (0..piece_count)
+            .filter(|index| !completed[*index])
+            .map(|index| {
+                let client = self.client.clone();
+                let url = self.url_from_path_id_and_suffix(path_id, suffix);
+                let retry_policy = self.retry_policy;
+                let download_semaphore = download_semaphore.clone();
+                let file_semaphore = file_semaphore.clone();
+                let download_path = download_path.clone();
+                let bitfield_path = bitfield_path.clone();
+                let completed_mutex = completed_mutex.clone();
+                let sender = sender.clone();
+                let path_id = path_id.clone();
+                let expected_piece_hash = pieces.hashes[index].clone();
+                let (start, end) = piece_range(index);
+                async move {
+                    let _download_permit = download_semaphore.acquire().await?;
+                    let _file_permit = file_semaphore.acquire().await?;
+                    // a piece fetch is a single bounded request rather than a stream, so it is
+                    // retried as a whole on the same transient errors `open_with_retries` retries
+                    // (connection/timeout/5xx/429), instead of going through that method directly
+                    // (which is built around `Transport::open`'s open-ended offset, not a
+                    // `start..end` range)
+                    let mut attempt = 0;
+                    let bytes = loop {
+                        let result = async {
+                            client
+                                .get(&url)
+                                .header(
+                                    reqwest::header::RANGE,
+                                    format!("bytes={start}-{}", end - 1),
+                                )
+                                .send()
+                                .await?
+                                .error_for_status()?
+                                .bytes()
+                                .await
+                        }
+                        .await;
+                        match result {
+                            Ok(bytes) => break bytes,
+                            Err(error)
+                                if attempt < retry_policy.max_attempts
+                                    && retryable_request_error(&error) =>
+                            {
+                                tokio::time::sleep(retry_policy.delay(attempt)).await;
+                                attempt += 1;
+                            }
+                            Err(error) => return Err(types::DownloadError::from(error)),
+                        }
+                    };
+                    if bytes.len() as u64 != end - start {
+                        return Err(types::DownloadError::Size {
+                            path_id: path_id.clone(),
+                            expected: end - start,
+                            downloaded: bytes.len() as u64,
+                        });
+                    }
+                    let mut hasher = expected_piece_hash.algorithm.hasher();
+                    hasher.update(&bytes);
+                    let digest = hasher.finalize();
+                    if digest != expected_piece_hash.digest {
+                        return Err(types::DownloadError::Piece {
+                            path_id: path_id.clone(),
+                            index,
+                        });
+                    }
+                    {
+                        let mut file = std::fs::File::options().write(true).open(&download_path)?;
+                        file.seek(std::io::SeekFrom::Start(start))?;
+                        file.write_all(&bytes)?;
+                    }
+                    {
+                        let mut completed = completed_mutex.lock().await;
+                        completed[index] = true;
+                        write_bitfield(&bitfield_path, &completed)?;
+                    }
+                    sender
+                        .send(
+                            types::RemoteProgress {
+                                path_id: path_id.clone(),
+                                initial_bytes: 0,
+                                current_bytes: (end - start) as i64,
+                                final_bytes: (end - start) as i64,
+                                complete: false,
+                            }
+                            .into(),
+                        )
+                        .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+                    Ok::<(), types::DownloadError>(())
+                }
+            })
+            .collect::<Vec<_>>();
+        for result in futures::future::join_all(pending_pieces).await {
+            result?;
+        }
+        let digest = types::Hash::hasher_from_reader(
+            expected_hash.algorithm,
+            std::fs::File::open(&download_path)?,
+        )?
+        .finalize();
+        if digest != expected_hash.digest {
+            return Err(types::DownloadError::Hash {
+                path_id: path_id.clone(),
+                downloaded: types::Hash {
+                    algorithm: expected_hash.algorithm,
+                    digest,
+                },
+                expected: expected_hash.clone(),
+            });
+        }
+        std::fs::rename(&download_path, &file_path)?;
+        let _ = std::fs::remove_file(&*bitfield_path);
+        sender
+            .send(
+                types::RemoteProgress {
+                    path_id: path_id.clone(),
+                    initial_bytes: 0,
+                    current_bytes: 0,
+                    final_bytes: 0,
+                    complete: true,
+                }
+                .into(),
+            )
+            .map_err(|_| types::DownloadError::Send(path_id.clone()))?;
+        Ok(())
+    }
 }
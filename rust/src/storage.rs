@@ -0,0 +1,159 @@
+/// Abstracts the handful of filesystem operations `decode::decompress` needs to land a decoded
+/// file (existence check, create, rename into place, remove the now-unneeded compressed variant),
+/// so the same decompression pipeline can write into a local directory or into an object-store
+/// bucket depending on how `Configuration::directory` was parsed by `Configuration::from_path`.
+/// The compressed variant `decompress` reads from is always staged on local disk by the download
+/// step beforehand, so reading it is not part of this trait. Every method is blocking, matching
+/// the `std::fs` calls it replaces and the fact that `decode::decompress` only ever runs inside
+/// `tokio::task::spawn_blocking`.
+pub trait Storage: std::fmt::Debug + Send + Sync {
+    /// Mirrors the `std::fs::metadata(path).is_file()` check `decompress` uses to skip work that
+    /// is already done when the caller did not pass `Force`.
+    fn is_file(&self, path: &std::path::Path) -> bool;
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn std::io::Write + Send>>;
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()>;
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()>;
+}
+
+/// The default backend, preserving today's behaviour exactly: every path handed to the trait
+/// methods is already an absolute local path (as produced by `types::PathRoot::join`), so each
+/// method is a thin pass-through to the matching `std::fs` function.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn is_file(&self, path: &std::path::Path) -> bool {
+        matches!(std::fs::metadata(path), Ok(metadata) if metadata.file_type().is_file())
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn std::io::Write + Send>> {
+        Ok(Box::new(std::fs::File::create(path)?))
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::remove_file(path)
+    }
+}
+
+fn other_error(error: object_store::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, error)
+}
+
+/// Backs `Storage` with an `object_store` client (`s3://`, `gs://`, `memory://`, `file://`)
+/// instead of the local filesystem. `root` is the same `PathBuf` `Configuration::directory`
+/// resolved to; it is stripped off the front of every path the trait methods receive before the
+/// remainder is turned into an `object_store::path::Path` key. `runtime` is the handle of the
+/// Tokio runtime `decode::decompress` blocks on (it only ever runs inside
+/// `tokio::task::spawn_blocking`), captured at construction time so every method below can
+/// `block_on` the underlying async client.
+#[derive(Clone)]
+pub struct ObjectStorage {
+    store: std::sync::Arc<dyn object_store::ObjectStore>,
+    root: std::path::PathBuf,
+    runtime: tokio::runtime::Handle,
+}
+
+impl std::fmt::Debug for ObjectStorage {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("ObjectStorage")
+            .field("root", &self.root)
+            .finish()
+    }
+}
+
+impl ObjectStorage {
+    /// Parses `url`'s scheme into the matching `object_store` client (returning its error if the
+    /// scheme is unsupported or the client cannot be built, for instance missing credentials).
+    /// Must be called from within a Tokio runtime, since every other method blocks on it.
+    pub fn new(url: &url::Url) -> Result<Self, object_store::Error> {
+        let (store, path) = object_store::parse_url(url)?;
+        let runtime = tokio::runtime::Handle::try_current().map_err(|error| {
+            object_store::Error::Generic {
+                store: "object_store",
+                source: Box::new(error),
+            }
+        })?;
+        Ok(ObjectStorage {
+            store: std::sync::Arc::from(store),
+            root: std::path::PathBuf::from(path.to_string()),
+            runtime,
+        })
+    }
+
+    fn key(&self, path: &std::path::Path) -> object_store::path::Path {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        object_store::path::Path::from(relative.to_string_lossy().replace('\\', "/").as_str())
+    }
+}
+
+/// Buffers writes in memory and `put`s them as a single object once dropped. Simple rather than
+/// streaming (a `put_multipart` upload would avoid holding the whole file in memory) — an
+/// acceptable first cut since `decompress` is only one step of migrating the install pipeline to
+/// object storage.
+struct ObjectWriter {
+    store: std::sync::Arc<dyn object_store::ObjectStore>,
+    runtime: tokio::runtime::Handle,
+    key: object_store::path::Path,
+    buffer: Vec<u8>,
+}
+
+impl std::io::Write for ObjectWriter {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ObjectWriter {
+    fn drop(&mut self) {
+        let payload = object_store::PutPayload::from(std::mem::take(&mut self.buffer));
+        let _ = self.runtime.block_on(self.store.put(&self.key, payload));
+    }
+}
+
+impl Storage for ObjectStorage {
+    fn is_file(&self, path: &std::path::Path) -> bool {
+        self.runtime
+            .block_on(self.store.head(&self.key(path)))
+            .is_ok()
+    }
+
+    fn create(&self, path: &std::path::Path) -> std::io::Result<Box<dyn std::io::Write + Send>> {
+        Ok(Box::new(ObjectWriter {
+            store: self.store.clone(),
+            runtime: self.runtime.clone(),
+            key: self.key(path),
+            buffer: Vec::new(),
+        }))
+    }
+
+    fn rename(&self, from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+        self.runtime
+            .block_on(self.store.rename(&self.key(from), &self.key(to)))
+            .map_err(other_error)
+    }
+
+    fn remove_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.runtime
+            .block_on(self.store.delete(&self.key(path)))
+            .map_err(other_error)
+    }
+}
+
+/// The object-store URL schemes `Configuration::from_path` recognises; anything else is treated
+/// as a local path, preserving today's behaviour exactly.
+pub fn is_object_store_scheme(scheme: &str) -> bool {
+    matches!(scheme, "s3" | "gs" | "memory" | "file")
+}
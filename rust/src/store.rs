@@ -0,0 +1,180 @@
+use crate::constants;
+use crate::types;
+use std::io::Read;
+
+lazy_static! {
+    // Gear hash lookup table: one pseudo-random u64 per byte value, used to roll a content-defined
+    // chunking hash over the byte stream (see `chunk_and_store` below).
+    static ref GEAR: [u64; 256] = {
+        let mut table = [0u64; 256];
+        let mut state: u64 = 0x243f6a8885a308d3;
+        for entry in table.iter_mut() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *entry = state;
+        }
+        table
+    };
+}
+
+pub fn blob_path(store_root: &std::path::Path, hash: &types::Hash) -> std::path::PathBuf {
+    let hex = hash.to_hex();
+    store_root.join(&hex[0..2]).join(hex)
+}
+
+pub fn contains(store_root: &std::path::Path, hash: &types::Hash) -> bool {
+    matches!(
+        std::fs::metadata(blob_path(store_root, hash)),
+        Ok(metadata) if metadata.file_type().is_file()
+    )
+}
+
+fn write_blob(
+    store_root: &std::path::Path,
+    hash: &types::Hash,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    if contains(store_root, hash) {
+        return Ok(());
+    }
+    let path = blob_path(store_root, hash);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, bytes)
+}
+
+/// Materializes the store's blob for `hash` at `destination` via a hardlink, falling back to a
+/// copy when `destination` is on a different filesystem. Returns `Ok(false)` without touching
+/// `destination` if `hash` isn't present in the store.
+pub fn link_file(
+    store_root: &std::path::Path,
+    hash: &types::Hash,
+    destination: &std::path::Path,
+) -> std::io::Result<bool> {
+    if !contains(store_root, hash) {
+        return Ok(false);
+    }
+    let path = blob_path(store_root, hash);
+    if std::fs::hard_link(&path, destination).is_err() {
+        std::fs::copy(&path, destination)?;
+    }
+    Ok(true)
+}
+
+/// Inserts the already-downloaded file at `source` into the store under its content hash via a
+/// hardlink, falling back to a copy. A no-op if the hash is already present.
+pub fn insert_file(
+    store_root: &std::path::Path,
+    hash: &types::Hash,
+    source: &std::path::Path,
+) -> std::io::Result<()> {
+    if contains(store_root, hash) {
+        return Ok(());
+    }
+    let path = blob_path(store_root, hash);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::hard_link(source, &path).is_err() {
+        std::fs::copy(source, &path)?;
+    }
+    Ok(())
+}
+
+/// Reassembles `destination` from chunk blobs already present in the store, in order.
+/// Returns `Ok(false)` without touching `destination` if any chunk is missing from the store.
+/// `install_directory` tries this before downloading a `Raw` resource with chunks, so a file that
+/// is fully deduplicated against what is already on disk never has its bytes fetched over the
+/// wire at all; a single absent chunk falls back to the ordinary download, since the remote only
+/// serves whole files/byte-ranges rather than individual chunks.
+pub fn assemble(
+    store_root: &std::path::Path,
+    chunks: &[crate::json_index::Chunk],
+    destination: &std::path::Path,
+) -> std::io::Result<bool> {
+    if chunks
+        .iter()
+        .any(|chunk| !contains(store_root, &chunk.hash))
+    {
+        return Ok(false);
+    }
+    let mut writer = std::fs::File::create(destination)?;
+    for chunk in chunks {
+        let mut reader = std::fs::File::open(blob_path(store_root, &chunk.hash))?;
+        std::io::copy(&mut reader, &mut writer)?;
+    }
+    Ok(true)
+}
+
+fn flush_chunk(
+    store_root: &std::path::Path,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<(types::Hash, u64)> {
+    let mut hasher = types::Algorithm::Sha3_224.hasher();
+    hasher.update(&buffer[..]);
+    let hash = types::Hash {
+        algorithm: types::Algorithm::Sha3_224,
+        digest: hasher.finalize(),
+    };
+    write_blob(store_root, &hash, buffer)?;
+    let length = buffer.len() as u64;
+    buffer.clear();
+    Ok((hash, length))
+}
+
+/// Splits `reader`'s bytes into content-defined chunks with a Gear-hash rolling boundary and
+/// writes each chunk's blob into the store keyed by its sha3-224 hash (a no-op when the blob is
+/// already present, which is what gives cross-file deduplication its disk savings).
+///
+/// Boundaries use FastCDC's normalized chunking: below the `CHUNK_AVERAGE_SIZE_LOG2` target size
+/// the rolling hash is masked with `mask_small` (more 1-bits, so a cut is less likely and chunks
+/// lean larger); at or above it, `mask_large` (fewer 1-bits, so a cut is more likely) pulls the
+/// boundary back toward the target instead of letting it drift. Cut evaluation is skipped below
+/// `CHUNK_MIN_SIZE` and a cut is forced at `CHUNK_MAX_SIZE` regardless of the rolling hash.
+pub fn chunk_and_store<R: Read>(
+    store_root: &std::path::Path,
+    mut reader: R,
+) -> std::io::Result<Vec<(types::Hash, u64)>> {
+    let average_size = 1usize << constants::CHUNK_AVERAGE_SIZE_LOG2;
+    let mask_small =
+        (1u64 << (constants::CHUNK_AVERAGE_SIZE_LOG2 + constants::CHUNK_NORMALIZATION_LEVEL)) - 1;
+    let mask_large =
+        (1u64 << (constants::CHUNK_AVERAGE_SIZE_LOG2 - constants::CHUNK_NORMALIZATION_LEVEL)) - 1;
+    let mut chunks = Vec::new();
+    let mut buffer = Vec::new();
+    let mut read_buffer = [0u8; constants::DECOMPRESS_CHUNK_SIZE];
+    let mut rolling_hash: u64 = 0;
+    loop {
+        let count = reader.read(&mut read_buffer)?;
+        if count == 0 {
+            break;
+        }
+        for &byte in &read_buffer[0..count] {
+            buffer.push(byte);
+            rolling_hash = (rolling_hash << 1).wrapping_add(GEAR[byte as usize]);
+            if buffer.len() >= constants::CHUNK_MAX_SIZE {
+                chunks.push(flush_chunk(store_root, &mut buffer)?);
+                rolling_hash = 0;
+                continue;
+            }
+            if buffer.len() < constants::CHUNK_MIN_SIZE {
+                continue;
+            }
+            let mask = if buffer.len() < average_size {
+                mask_small
+            } else {
+                mask_large
+            };
+            if rolling_hash & mask == 0 {
+                chunks.push(flush_chunk(store_root, &mut buffer)?);
+                rolling_hash = 0;
+            }
+        }
+    }
+    if !buffer.is_empty() {
+        chunks.push(flush_chunk(store_root, &mut buffer)?);
+    }
+    Ok(chunks)
+}
@@ -0,0 +1,217 @@
+use crate::constants;
+use crate::types;
+use futures::TryStreamExt;
+
+type ByteStream = std::pin::Pin<Box<dyn tokio::io::AsyncRead + Unpin + Send>>;
+
+/// Parses a `206 Partial Content` response's `Content-Range: bytes start-end/total` header and
+/// checks that `start` matches the offset that was requested. A missing or unparsable header, or
+/// one that starts somewhere else, is treated as a failed resume rather than trusted blindly.
+fn content_range_starts_at(response: &reqwest::Response, offset: u64) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("bytes "))
+        .and_then(|value| value.split('-').next())
+        .and_then(|start| start.parse::<u64>().ok())
+        == Some(offset)
+}
+
+/// Backend that `Server` reads a resource's bytes from. Abstracts away whatever the URL scheme
+/// implies (HTTP Range requests, a random-access local file, an SFTP session, ...) behind the one
+/// operation `start_download`/`download_file` actually need, so the `.download` staging, hashing
+/// and progress-reporting machinery in `remote.rs` stays the same no matter where the bytes
+/// physically come from.
+#[async_trait::async_trait]
+pub trait Transport: Send + Sync + std::fmt::Debug {
+    /// Opens a byte stream for `url`, resuming from `offset` if the backend supports it. The
+    /// returned `bool` is `true` if the stream starts at `offset` (the caller keeps whatever is
+    /// already on disk) or `false` if it starts at byte 0 (the caller must discard it and
+    /// restart).
+    async fn open(
+        &self,
+        url: &str,
+        offset: u64,
+    ) -> Result<(ByteStream, bool), types::DownloadError>;
+}
+
+/// The original transport: plain HTTP(S) `GET` requests, resumed via `Range` headers.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        HttpTransport { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for HttpTransport {
+    async fn open(
+        &self,
+        url: &str,
+        offset: u64,
+    ) -> Result<(ByteStream, bool), types::DownloadError> {
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+        // a non-2xx status becomes a `reqwest::Error` carrying that status, so
+        // `remote::Server::open_with_retries` can tell a transient 5xx/429 apart from a
+        // permanent 4xx instead of silently streaming an error page as if it were the resource
+        let response = request.send().await?.error_for_status()?;
+        // a server that ignores `Range` (200 instead of 206) is the common case, but one that
+        // claims 206 while actually answering from a different offset (a misconfigured proxy or
+        // CDN) is just as unsafe to append to; `start_download`'s `on_range_failed` fallback
+        // handles both the same way, by discarding the partial and restarting from byte 0
+        let resumed = offset == 0
+            || (response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+                && content_range_starts_at(&response, offset));
+        let stream = response
+            .bytes_stream()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error));
+        Ok((Box::pin(tokio_util::io::StreamReader::new(stream)), resumed))
+    }
+}
+
+/// Reads from a local (or network-mounted) directory tree addressed with a `file://` URL, e.g. a
+/// NAS mirror of a dataset. Resuming is trivial: local files support random access, so every open
+/// "resumes" exactly at `offset`.
+#[derive(Debug, Clone)]
+pub struct FileTransport;
+
+impl FileTransport {
+    fn path_from_url(url: &str) -> &str {
+        url.strip_prefix("file://").unwrap_or(url)
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for FileTransport {
+    async fn open(
+        &self,
+        url: &str,
+        offset: u64,
+    ) -> Result<(ByteStream, bool), types::DownloadError> {
+        use tokio::io::AsyncSeekExt;
+        let mut file = tokio::fs::File::open(Self::path_from_url(url)).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        Ok((Box::pin(file), true))
+    }
+}
+
+fn ssh_error_to_io_error(error: ssh2::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, error)
+}
+
+/// Reads from an SFTP mirror over an SSH session authenticated via the local SSH agent (the same
+/// mechanism `ssh`/`scp` use, so no credentials are handled by undr itself). The connection and
+/// SFTP read loop run on a blocking task, since the underlying `ssh2` session is synchronous;
+/// bytes are relayed to the async caller over a bounded channel.
+#[derive(Debug, Clone)]
+pub struct SftpTransport {
+    host: String,
+    port: u16,
+    username: String,
+}
+
+impl SftpTransport {
+    pub fn new(base_url: &url::Url) -> Self {
+        SftpTransport {
+            host: base_url.host_str().unwrap_or("localhost").to_owned(),
+            port: base_url.port().unwrap_or(22),
+            username: if base_url.username().is_empty() {
+                "undr".to_owned()
+            } else {
+                base_url.username().to_owned()
+            },
+        }
+    }
+
+    /// Strips `sftp://user@host:port` down to the remote filesystem path `sftp.open` expects, the
+    /// same way `FileTransport::path_from_url` strips `file://`.
+    fn path_from_url(url: &str) -> String {
+        url::Url::parse(url)
+            .map(|parsed| parsed.path().to_owned())
+            .unwrap_or_else(|_| url.to_owned())
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for SftpTransport {
+    async fn open(
+        &self,
+        url: &str,
+        offset: u64,
+    ) -> Result<(ByteStream, bool), types::DownloadError> {
+        let remote_path = Self::path_from_url(url);
+        let host = self.host.clone();
+        let port = self.port;
+        let username = self.username.clone();
+        let (sender, receiver) = tokio::sync::mpsc::channel::<std::io::Result<bytes::Bytes>>(4);
+        let (resumed_sender, resumed_receiver) = tokio::sync::oneshot::channel::<bool>();
+        tokio::task::spawn_blocking(move || {
+            let result = (|| -> std::io::Result<()> {
+                let tcp_stream = std::net::TcpStream::connect((host.as_str(), port))?;
+                let mut session = ssh2::Session::new().map_err(ssh_error_to_io_error)?;
+                session.set_tcp_stream(tcp_stream);
+                session.handshake().map_err(ssh_error_to_io_error)?;
+                session
+                    .userauth_agent(&username)
+                    .map_err(ssh_error_to_io_error)?;
+                let sftp = session.sftp().map_err(ssh_error_to_io_error)?;
+                let mut remote_file = sftp
+                    .open(std::path::Path::new(&remote_path))
+                    .map_err(ssh_error_to_io_error)?;
+                // seeking past the end of the remote file does not itself fail (the SFTP client
+                // only records the offset, it does not round-trip), so the remote size is checked
+                // first; a corrupt/stale offset falls back to restarting from byte 0 rather than
+                // silently handing the caller a short read it would mistake for a clean resume
+                let size = remote_file
+                    .stat()
+                    .map_err(ssh_error_to_io_error)?
+                    .size
+                    .unwrap_or(0);
+                let resumed = offset <= size;
+                remote_file.seek(if resumed { offset } else { 0 });
+                let _ = resumed_sender.send(resumed);
+                let mut buffer = [0u8; constants::DECOMPRESS_CHUNK_SIZE];
+                loop {
+                    let count = std::io::Read::read(&mut remote_file, &mut buffer)?;
+                    if count == 0 {
+                        break;
+                    }
+                    if sender
+                        .blocking_send(Ok(bytes::Bytes::copy_from_slice(&buffer[0..count])))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Ok(())
+            })();
+            if let Err(error) = result {
+                let _ = sender.blocking_send(Err(error));
+            }
+        });
+        let stream = tokio_stream::wrappers::ReceiverStream::new(receiver);
+        // `false` only if the blocking task died before reaching the seek (e.g. the connection or
+        // handshake failed); the real error still reaches the caller via the first item `stream`
+        // yields, so this fallback value is never actually read as a successful resume
+        let resumed = resumed_receiver.await.unwrap_or(false);
+        Ok((Box::pin(tokio_util::io::StreamReader::new(stream)), resumed))
+    }
+}
+
+/// Picks the `Transport` implementation matching `url`'s scheme. Unrecognized schemes fall back
+/// to HTTP, matching `Server`'s behaviour before transports became pluggable.
+pub fn from_url(url: &url::Url, client: reqwest::Client) -> std::sync::Arc<dyn Transport> {
+    match url.scheme() {
+        "file" => std::sync::Arc::new(FileTransport),
+        "sftp" => std::sync::Arc::new(SftpTransport::new(url)),
+        _ => std::sync::Arc::new(HttpTransport::new(client)),
+    }
+}
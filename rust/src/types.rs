@@ -1,24 +1,43 @@
 use crate::constants;
+use digest::Digest;
 use serde::de::Error;
-use sha3::Digest;
 use std::io::Read;
 
 lazy_static! {
     static ref NAME_REGEX: regex::Regex = regex::Regex::new(r"^[A-Za-z0-9_\-.]+$").unwrap();
-    static ref HASH_REGEX: regex::Regex = regex::Regex::new(r"^[a-f0-9]{56}$").unwrap();
     static ref DOI_REGEX: regex::Regex = regex::Regex::new(r"^10[.].+$").unwrap();
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct Name(pub String);
 
+impl serde::Serialize for Name {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // CBOR has no reason to pay the text-string-vs-byte-string overhead twice over: the
+        // validated string is already ASCII, so its UTF-8 bytes are its natural binary form
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.0)
+        } else {
+            serializer.serialize_bytes(self.0.as_bytes())
+        }
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for Name {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let string = String::deserialize(deserializer)?;
+        let string = if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+        } else {
+            String::from_utf8(<Vec<u8>>::deserialize(deserializer)?)
+                .map_err(|error| D::Error::custom(error.to_string()))?
+        };
         if NAME_REGEX.is_match(&string) {
             return Ok(Name(string));
         }
@@ -45,16 +64,32 @@ impl From<Name> for PathId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-#[repr(transparent)]
-pub struct PathRoot(pub std::sync::Arc<std::path::PathBuf>);
+#[derive(Debug, Clone)]
+pub struct PathRoot {
+    directory: std::sync::Arc<std::path::PathBuf>,
+    storage: std::sync::Arc<dyn crate::storage::Storage>,
+}
 
 impl PathRoot {
+    pub fn new(
+        directory: std::sync::Arc<std::path::PathBuf>,
+        storage: std::sync::Arc<dyn crate::storage::Storage>,
+    ) -> PathRoot {
+        PathRoot { directory, storage }
+    }
+
+    /// The backend `decode::decompress` writes the decoded file to (it reads the compressed variant
+    /// from the local filesystem directly): the local filesystem unless `Configuration::directory`
+    /// parsed as an object-store URL.
+    pub fn storage(&self) -> &dyn crate::storage::Storage {
+        self.storage.as_ref()
+    }
+
     pub fn join(&self, path_id: &PathId) -> std::path::PathBuf {
         if std::path::MAIN_SEPARATOR == '/' {
-            self.0.join(&path_id.0)
+            self.directory.join(&path_id.0)
         } else {
-            self.0.join(
+            self.directory.join(
                 path_id
                     .0
                     .chars()
@@ -85,17 +120,374 @@ impl PathRoot {
             path_id.0, first_suffix, second_suffix
         )))
     }
+
+    /// Path of the per-root completed-install ledger (see `crate::ledger`), shared by every
+    /// dataset rooted under this `PathRoot` the same way `chunk_store_root` is.
+    pub fn ledger_path(&self) -> std::path::PathBuf {
+        self.directory.join(constants::LEDGER_FILE_NAME)
+    }
+
+    /// Directory next to the root directory where content-addressed chunk blobs are stored,
+    /// shared across every dataset rooted under this `PathRoot` so identical chunks
+    /// (duplicated recordings, repeated headers, ...) are only ever stored once.
+    pub fn chunk_store_root(&self) -> std::path::PathBuf {
+        match self.directory.file_name() {
+            Some(name) => {
+                let mut sibling_name = name.to_os_string();
+                sibling_name.push(constants::CHUNK_STORE_SUFFIX);
+                self.directory.with_file_name(sibling_name)
+            }
+            None => self.directory.join(constants::CHUNK_STORE_SUFFIX),
+        }
+    }
+}
+
+/// The three states a running action can be in. `Stopped` is the old hard-cancel (a worker loop
+/// observing it propagates an early return up through its `JoinSet`); `Paused` sits alongside it
+/// so a worker can suspend at a file boundary instead of being torn down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum RunState {
+    Running = 0,
+    Paused = 1,
+    Stopped = 2,
+}
+
+struct RunControlInner {
+    state: std::sync::atomic::AtomicU8,
+    notify: tokio::sync::Notify,
+}
+
+/// Cooperative run control shared into every long-running worker loop (`install_directory`,
+/// `uninstall_directory`, `plan_directory`, `verify_directory`, ...); the per-DOI fetches of
+/// `Configuration::bibtex` do not check it.
+/// Cloning is cheap; every clone refers to the same underlying state. `pause`/`resume` let a
+/// worker suspend between files without discarding progress already made, and `wait_if_paused`
+/// blocks the caller rather than having it busy-spin on the flag.
+#[derive(Clone)]
+pub struct RunControl(std::sync::Arc<RunControlInner>);
+
+impl std::fmt::Debug for RunControl {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_tuple("RunControl")
+            .field(&self.0.state.load(std::sync::atomic::Ordering::Acquire))
+            .finish()
+    }
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        RunControl::new()
+    }
+}
+
+impl RunControl {
+    pub fn new() -> RunControl {
+        RunControl(std::sync::Arc::new(RunControlInner {
+            state: std::sync::atomic::AtomicU8::new(RunState::Running as u8),
+            notify: tokio::sync::Notify::new(),
+        }))
+    }
+
+    pub fn stop(&self) {
+        self.0
+            .state
+            .store(RunState::Stopped as u8, std::sync::atomic::Ordering::Release);
+        self.0.notify.notify_waiters();
+    }
+
+    pub fn pause(&self) {
+        // only a `Running` action can be paused: pausing a stopped one should not un-stop it
+        let _ = self.0.state.compare_exchange(
+            RunState::Running as u8,
+            RunState::Paused as u8,
+            std::sync::atomic::Ordering::AcqRel,
+            std::sync::atomic::Ordering::Acquire,
+        );
+    }
+
+    pub fn resume(&self) {
+        if self
+            .0
+            .state
+            .compare_exchange(
+                RunState::Paused as u8,
+                RunState::Running as u8,
+                std::sync::atomic::Ordering::AcqRel,
+                std::sync::atomic::Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            self.0.notify.notify_waiters();
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.state.load(std::sync::atomic::Ordering::Acquire) == RunState::Stopped as u8
+    }
+
+    /// Blocks while paused, returning as soon as the control is resumed or stopped. Worker loops
+    /// call this between files so they suspend at a boundary instead of busy-spinning.
+    pub async fn wait_if_paused(&self) {
+        loop {
+            if self.0.state.load(std::sync::atomic::Ordering::Acquire) != RunState::Paused as u8 {
+                return;
+            }
+            let notified = self.0.notify.notified();
+            if self.0.state.load(std::sync::atomic::Ordering::Acquire) != RunState::Paused as u8 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A `tokio::sync::Semaphore` whose permit count can be changed after construction. Growing adds
+/// permits immediately. Shrinking forgets whatever permits are free right now and remembers any
+/// shortfall as debt; the next `acquire`/`acquire_owned` calls each pay down one unit of that debt
+/// by forgetting the permit they just acquired and acquiring another in its place, instead of
+/// handing it to their caller. This means a shrink converges to the target as in-flight work
+/// completes rather than applying atomically — there is no way to revoke a permit a caller is
+/// already holding.
+pub struct AdaptiveSemaphore {
+    semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+    total: std::sync::atomic::AtomicUsize,
+    owed: std::sync::atomic::AtomicUsize,
+}
+
+impl AdaptiveSemaphore {
+    pub fn new(permits: usize) -> AdaptiveSemaphore {
+        AdaptiveSemaphore {
+            semaphore: std::sync::Arc::new(tokio::sync::Semaphore::new(permits)),
+            total: std::sync::atomic::AtomicUsize::new(permits),
+            owed: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    pub fn set_permits(&self, target: usize) {
+        let current = self
+            .total
+            .swap(target, std::sync::atomic::Ordering::AcqRel);
+        if target > current {
+            self.semaphore.add_permits(target - current);
+        } else if target < current {
+            let shrink = current - target;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            self.owed
+                .fetch_add(shrink - forgotten, std::sync::atomic::Ordering::AcqRel);
+        }
+    }
+
+    fn pay_down_debt(&self) -> bool {
+        self.owed
+            .fetch_update(
+                std::sync::atomic::Ordering::AcqRel,
+                std::sync::atomic::Ordering::Acquire,
+                |owed| if owed > 0 { Some(owed - 1) } else { None },
+            )
+            .is_ok()
+    }
+
+    pub async fn acquire_owned(
+        &self,
+    ) -> Result<tokio::sync::OwnedSemaphorePermit, tokio::sync::AcquireError> {
+        loop {
+            let permit = self.semaphore.clone().acquire_owned().await?;
+            if self.pay_down_debt() {
+                permit.forget();
+                continue;
+            }
+            return Ok(permit);
+        }
+    }
+
+    pub async fn acquire(
+        &self,
+    ) -> Result<tokio::sync::SemaphorePermit<'_>, tokio::sync::AcquireError> {
+        loop {
+            let permit = self.semaphore.acquire().await?;
+            if self.pay_down_debt() {
+                permit.forget();
+                continue;
+            }
+            return Ok(permit);
+        }
+    }
+}
+
+/// Bundles the `RunControl` that lets a caller pause/resume/stop `Configuration::install` with
+/// the two `AdaptiveSemaphore`s that gate how many downloads and decodes run at once, so a caller
+/// holding on to one `InstallControl` can retune both kinds of concurrency while the install is
+/// in flight instead of only being able to cancel it.
+#[derive(Clone)]
+pub struct InstallControl {
+    run_control: RunControl,
+    download_semaphore: std::sync::Arc<AdaptiveSemaphore>,
+    decode_semaphore: std::sync::Arc<AdaptiveSemaphore>,
+}
+
+impl InstallControl {
+    pub fn new(
+        run_control: RunControl,
+        download_permits: DownloadPermits,
+        decode_permits: DecodePermits,
+    ) -> InstallControl {
+        InstallControl {
+            run_control,
+            download_semaphore: std::sync::Arc::new(AdaptiveSemaphore::new(download_permits.0)),
+            decode_semaphore: std::sync::Arc::new(AdaptiveSemaphore::new(decode_permits.0)),
+        }
+    }
+
+    pub fn run_control(&self) -> RunControl {
+        self.run_control.clone()
+    }
+
+    pub fn download_semaphore(&self) -> std::sync::Arc<AdaptiveSemaphore> {
+        self.download_semaphore.clone()
+    }
+
+    pub fn decode_semaphore(&self) -> std::sync::Arc<AdaptiveSemaphore> {
+        self.decode_semaphore.clone()
+    }
+
+    pub fn set_download_permits(&self, permits: DownloadPermits) {
+        self.download_semaphore.set_permits(permits.0);
+    }
+
+    pub fn set_decode_permits(&self, permits: DecodePermits) {
+        self.decode_semaphore.set_permits(permits.0);
+    }
+}
+
+/// Digest algorithms a resource's declared hash may use. `Sha3_224` is UNDR's original and
+/// default algorithm; the others let mirrored datasets keep the digest their upstream provider
+/// already publishes instead of forcing a full rehash. `Sha1` exists solely to verify legacy
+/// manifests published before UNDR switched to Sha3-224; do not use it for new datasets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Sha3_224,
+    Sha2_256,
+    Blake3,
+    Sha1,
+}
+
+impl Algorithm {
+    fn tag(self) -> &'static str {
+        match self {
+            Algorithm::Sha3_224 => "sha3-224",
+            Algorithm::Sha2_256 => "sha2-256",
+            Algorithm::Blake3 => "blake3",
+            Algorithm::Sha1 => "sha1",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Algorithm> {
+        match tag {
+            "sha3-224" => Some(Algorithm::Sha3_224),
+            "sha2-256" => Some(Algorithm::Sha2_256),
+            "blake3" => Some(Algorithm::Blake3),
+            "sha1" => Some(Algorithm::Sha1),
+            _ => None,
+        }
+    }
+
+    // the one-byte tag `Hash`'s CBOR form prefixes the digest with, in place of the `tag()`
+    // string used by the human-readable ("sha3-224:<hex>") form
+    fn code(self) -> u8 {
+        match self {
+            Algorithm::Sha3_224 => 0,
+            Algorithm::Sha2_256 => 1,
+            Algorithm::Blake3 => 2,
+            Algorithm::Sha1 => 3,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Algorithm> {
+        match code {
+            0 => Some(Algorithm::Sha3_224),
+            1 => Some(Algorithm::Sha2_256),
+            2 => Some(Algorithm::Blake3),
+            3 => Some(Algorithm::Sha1),
+            _ => None,
+        }
+    }
+
+    fn digest_length(self) -> usize {
+        match self {
+            Algorithm::Sha3_224 => 28,
+            Algorithm::Sha2_256 => 32,
+            Algorithm::Blake3 => 32,
+            Algorithm::Sha1 => 20,
+        }
+    }
+
+    pub fn hasher(self) -> Box<dyn StreamingHasher + Send> {
+        match self {
+            Algorithm::Sha3_224 => Box::new(sha3::Sha3_224::new()),
+            Algorithm::Sha2_256 => Box::new(sha2::Sha256::new()),
+            Algorithm::Blake3 => Box::new(blake3::Hasher::new()),
+            Algorithm::Sha1 => Box::new(sha1::Sha1::new()),
+        }
+    }
+}
+
+/// A streaming digest, boxed so that `Hash::hasher_from_reader` and the download / decompress
+/// verification paths do not need to be generic over the chosen `Algorithm`.
+pub trait StreamingHasher {
+    fn update(&mut self, bytes: &[u8]);
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+impl StreamingHasher for sha3::Sha3_224 {
+    fn update(&mut self, bytes: &[u8]) {
+        digest::Digest::update(self, bytes);
+    }
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        digest::Digest::finalize(*self).to_vec()
+    }
+}
+
+impl StreamingHasher for sha2::Sha256 {
+    fn update(&mut self, bytes: &[u8]) {
+        digest::Digest::update(self, bytes);
+    }
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        digest::Digest::finalize(*self).to_vec()
+    }
+}
+
+impl StreamingHasher for blake3::Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        blake3::Hasher::update(self, bytes);
+    }
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        blake3::Hasher::finalize(&self).as_bytes().to_vec()
+    }
+}
+
+impl StreamingHasher for sha1::Sha1 {
+    fn update(&mut self, bytes: &[u8]) {
+        digest::Digest::update(self, bytes);
+    }
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        digest::Digest::finalize(*self).to_vec()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-#[repr(transparent)]
-pub struct Hash(
-    pub generic_array::GenericArray<u8, <sha3::Sha3_224 as digest::OutputSizeUser>::OutputSize>,
-);
+pub struct Hash {
+    pub algorithm: Algorithm,
+    pub digest: Vec<u8>,
+}
 
 impl Hash {
-    pub fn hasher_from_reader<R: Read>(mut reader: R) -> Result<sha3::Sha3_224, std::io::Error> {
-        let mut hasher = sha3::Sha3_224::new();
+    pub fn hasher_from_reader<R: Read>(
+        algorithm: Algorithm,
+        mut reader: R,
+    ) -> Result<Box<dyn StreamingHasher + Send>, std::io::Error> {
+        let mut hasher = algorithm.hasher();
         let mut buffer = [0; constants::DECOMPRESS_CHUNK_SIZE];
         loop {
             let count = reader.read(&mut buffer)?;
@@ -106,6 +498,14 @@ impl Hash {
         }
         Ok(hasher)
     }
+
+    pub fn to_hex(&self) -> String {
+        self.digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<String>>()
+            .join("")
+    }
 }
 
 impl<'de> serde::Deserialize<'de> for Hash {
@@ -113,21 +513,51 @@ impl<'de> serde::Deserialize<'de> for Hash {
     where
         D: serde::Deserializer<'de>,
     {
+        if !deserializer.is_human_readable() {
+            // the binary form is the algorithm's `code()` byte followed by the raw digest, in
+            // place of the "<tag>:<hex>" string the human-readable form uses
+            let bytes = <Vec<u8>>::deserialize(deserializer)?;
+            let (&code, digest) = bytes
+                .split_first()
+                .ok_or_else(|| D::Error::custom("empty hash bytes"))?;
+            let algorithm = Algorithm::from_code(code)
+                .ok_or_else(|| D::Error::custom(format!("unknown hash algorithm code {code}")))?;
+            if digest.len() != algorithm.digest_length() {
+                return Err(D::Error::custom(
+                    "the byte string does not match the pattern \"hash\"",
+                ));
+            }
+            return Ok(Hash {
+                algorithm,
+                digest: digest.to_vec(),
+            });
+        }
         let string = String::deserialize(deserializer)?;
-        if HASH_REGEX.is_match(&string) {
-            return Ok(Hash(
-                generic_array::GenericArray::<
-                    u8,
-                    <sha3::Sha3_224 as digest::OutputSizeUser>::OutputSize,
-                >::from_exact_iter(string.as_bytes().chunks(2).map(|pair| {
-                    u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap()
-                }))
-                .unwrap(),
+        // a bare hex digest (no "<algorithm>:" prefix) is accepted as sha3-224 for backward
+        // compatibility with indices published before hash algorithms became pluggable
+        let (algorithm, hex_digest) = match string.split_once(':') {
+            Some((tag, hex_digest)) => (
+                Algorithm::from_tag(tag)
+                    .ok_or_else(|| D::Error::custom(format!("unknown hash algorithm \"{tag}\"")))?,
+                hex_digest,
+            ),
+            None => (Algorithm::Sha3_224, string.as_str()),
+        };
+        if hex_digest.len() != algorithm.digest_length() * 2
+            || !hex_digest.bytes().all(|byte| byte.is_ascii_hexdigit())
+        {
+            return Err(D::Error::custom(
+                "the string does not match the pattern \"hash\"",
             ));
         }
-        Err(D::Error::custom(
-            "the string does not match the pattern \"hash\"",
-        ))
+        Ok(Hash {
+            algorithm,
+            digest: hex_digest
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+                .collect(),
+        })
     }
 }
 
@@ -136,26 +566,44 @@ impl serde::Serialize for Hash {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(
-            &self
-                .0
-                .iter()
-                .map(|byte| format!("{byte:02x}"))
-                .collect::<Vec<String>>()
-                .join(""),
-        )
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("{}:{}", self.algorithm.tag(), self.to_hex()))
+        } else {
+            let mut bytes = Vec::with_capacity(1 + self.digest.len());
+            bytes.push(self.algorithm.code());
+            bytes.extend_from_slice(&self.digest);
+            serializer.serialize_bytes(&bytes)
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Doi(pub String);
 
+impl serde::Serialize for Doi {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.0)
+        } else {
+            serializer.serialize_bytes(self.0.as_bytes())
+        }
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for Doi {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let string = String::deserialize(deserializer)?;
+        let string = if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+        } else {
+            String::from_utf8(<Vec<u8>>::deserialize(deserializer)?)
+                .map_err(|error| D::Error::custom(error.to_string()))?
+        };
         if DOI_REGEX.is_match(&string) {
             return Ok(Doi(string));
         }
@@ -195,6 +643,22 @@ pub struct Report {
     pub remote_bytes: u64,
 }
 
+/// A single filesystem removal that failed while uninstalling a dataset, kept alongside whatever
+/// removals succeeded rather than aborting the rest of the walk.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UninstallFailure {
+    pub path_id: PathId,
+    pub error: String,
+}
+
+/// A resource whose local copy is missing or whose recomputed hash does not match its index
+/// entry, surfaced by `verify` so the caller can decide whether to repair it.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VerifyMismatch {
+    pub path_id: PathId,
+    pub reason: String,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct DirectoryScanned {
     pub path_id: PathId,
@@ -230,6 +694,12 @@ pub enum DownloadError {
         downloaded: u64,
     },
 
+    #[error("piece error")]
+    Piece { path_id: PathId, index: usize },
+
+    #[error("retries exhausted")]
+    RetriesExhausted { path_id: PathId },
+
     #[error("send error")]
     Send(PathId),
 
@@ -280,12 +750,18 @@ pub enum ActionError {
     #[error("directory error")]
     Directory(#[from] std::io::Error),
 
+    #[error("object store error")]
+    Storage(#[from] object_store::Error),
+
     #[error("read error")]
     Read(std::path::PathBuf),
 
     #[error("index parse error")]
     Parse(#[from] serde_json::Error),
 
+    #[error("index decode error")]
+    Decode(#[from] ciborium::de::Error<std::io::Error>),
+
     #[error("semaphore error")]
     Semaphore(#[from] tokio::sync::AcquireError),
 
@@ -297,6 +773,9 @@ pub enum ActionError {
 
     #[error("DOI send error")]
     DoiSend,
+
+    #[error("{} dataset(s) failed", .0.len())]
+    Partial(Vec<(PathId, ActionError)>),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -305,6 +784,9 @@ pub enum DoiStatus {
     #[serde(rename = "start")]
     Start,
 
+    #[serde(rename = "retrying")]
+    Retrying { attempt: u32, max_attempts: u32 },
+
     #[serde(rename = "success")]
     Success(String),
 
@@ -312,6 +794,69 @@ pub enum DoiStatus {
     Error(String),
 }
 
+/// Truncated exponential backoff with jitter, shared by the DOI fetch in `Configuration::bibtex`
+/// and (via `remote::Server`) the download retries in `Configuration::install`. `max_attempts`
+/// bounds how many times a request is retried after its first failure; `base_delay`/`max_delay`
+/// (seconds) bound how long each wait grows to before jitter is applied.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: f64,
+    pub max_delay: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: constants::DEFAULT_MAX_RETRIES,
+            base_delay: constants::RETRY_BASE_DELAY.as_secs_f64(),
+            max_delay: constants::RETRY_MAX_DELAY.as_secs_f64(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `base_delay * 2^attempt` (capped at `max_delay`) multiplied by a random factor in
+    /// `[0.5, 1.0]`, so that many clients retrying the same transient outage do not all
+    /// reconnect at the same instant. `attempt` is 0-indexed (the delay before the first retry).
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        use std::hash::{Hash, Hasher};
+        let base = std::time::Duration::from_secs_f64(self.base_delay)
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(std::time::Duration::from_secs_f64(self.max_delay));
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::time::SystemTime::now().hash(&mut hasher);
+        attempt.hash(&mut hasher);
+        let jitter_fraction = (hasher.finish() % 1000) as f64 / 1000.0;
+        base.mul_f64(0.5 + jitter_fraction * 0.5)
+    }
+
+    /// A 5xx or 429 response is treated as transient; any other status (permanent client errors
+    /// like 404/401) is not retried.
+    pub fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// Honors a numeric `Retry-After` header (HTTP-date values are rare enough from `doi.org`
+    /// that they are not parsed) by extending the computed backoff up to that many seconds;
+    /// otherwise falls back to `self.delay(attempt)`.
+    pub fn delay_for_response(
+        &self,
+        attempt: u32,
+        headers: &reqwest::header::HeaderMap,
+    ) -> std::time::Duration {
+        let computed = self.delay(attempt);
+        match headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            Some(seconds) => computed.max(std::time::Duration::from_secs(seconds)),
+            None => computed,
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(tag = "type")]
 pub enum Message {
@@ -337,6 +882,31 @@ pub enum Message {
         #[serde(flatten)]
         status: DoiStatus,
     },
+
+    #[serde(rename = "uninstall_progress")]
+    UninstallProgress {
+        path_id: PathId,
+        error: Option<String>,
+    },
+
+    #[serde(rename = "verify_progress")]
+    VerifyProgress {
+        path_id: PathId,
+        mismatch: Option<String>,
+    },
+
+    #[serde(rename = "task_failed")]
+    TaskFailed { path_id: PathId, error: String },
+
+    #[serde(rename = "verified")]
+    Verified { path_id: PathId },
+
+    #[serde(rename = "verify_failed")]
+    VerifyFailed {
+        path_id: PathId,
+        expected: Hash,
+        actual: Hash,
+    },
 }
 
 impl From<RemoteProgress> for Message {
@@ -355,12 +925,41 @@ impl From<DecodeProgress> for Message {
 mod tests {
     #[test]
     fn test_hash_serde() {
-        let hash_json = "\"10ada4f8679a20c4d4f8fea56e8552e667f01a405611ca8c0463546c\"";
+        let hash_json = "\"sha3-224:10ada4f8679a20c4d4f8fea56e8552e667f01a405611ca8c0463546c\"";
         let hash: crate::types::Hash = serde_json::from_str(&hash_json).unwrap();
         let hash_json_2 = serde_json::to_string(&hash).unwrap();
         assert_eq!(hash_json, hash_json_2);
     }
 
+    #[test]
+    fn test_hash_cbor_round_trip() {
+        let hash = crate::types::Hash {
+            algorithm: crate::types::Algorithm::Sha3_224,
+            digest: vec![0xab; crate::types::Algorithm::Sha3_224.digest_length()],
+        };
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&hash, &mut cbor).unwrap();
+        // a CBOR-encoded `Hash` carries the raw digest bytes (plus a one-byte algorithm tag)
+        // rather than the "<algorithm>:<hex>" string the JSON form uses, so it is noticeably
+        // smaller than re-encoding that string would be
+        assert!(cbor.len() < hash.to_hex().len());
+        let decoded: crate::types::Hash = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(decoded.algorithm, hash.algorithm);
+        assert_eq!(decoded.digest, hash.digest);
+    }
+
+    #[test]
+    fn test_hash_bare_hex_backward_compat() {
+        let hash_json = "\"10ada4f8679a20c4d4f8fea56e8552e667f01a405611ca8c0463546c\"";
+        let hash: crate::types::Hash = serde_json::from_str(&hash_json).unwrap();
+        assert_eq!(hash.algorithm, crate::types::Algorithm::Sha3_224);
+        let hash_json_2 = serde_json::to_string(&hash).unwrap();
+        assert_eq!(
+            hash_json_2,
+            "\"sha3-224:10ada4f8679a20c4d4f8fea56e8552e667f01a405611ca8c0463546c\""
+        );
+    }
+
     #[test]
     fn test_bibtex_message() {
         println!(
@@ -387,6 +986,54 @@ mod tests {
             })
             .unwrap()
         );
+        println!(
+            "{}",
+            serde_json::to_string(&crate::Message::DoiProgress {
+                value: crate::types::Doi("10.test".to_owned()),
+                status: crate::types::DoiStatus::Retrying {
+                    attempt: 2,
+                    max_attempts: 5,
+                },
+            })
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify_message() {
+        println!(
+            "{}",
+            serde_json::to_string(&crate::Message::Verified {
+                path_id: crate::types::PathId("test".to_owned()),
+            })
+            .unwrap()
+        );
+        let hash = crate::types::Hash {
+            algorithm: crate::types::Algorithm::Sha3_224,
+            digest: vec![0; crate::types::Algorithm::Sha3_224.digest_length()],
+        };
+        println!(
+            "{}",
+            serde_json::to_string(&crate::Message::VerifyFailed {
+                path_id: crate::types::PathId("test".to_owned()),
+                expected: hash.clone(),
+                actual: hash,
+            })
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_is_retryable_status() {
+        assert!(crate::types::RetryPolicy::is_retryable_status(
+            reqwest::StatusCode::TOO_MANY_REQUESTS
+        ));
+        assert!(crate::types::RetryPolicy::is_retryable_status(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert!(!crate::types::RetryPolicy::is_retryable_status(
+            reqwest::StatusCode::NOT_FOUND
+        ));
     }
 }
 
@@ -394,6 +1041,16 @@ mod tests {
 #[repr(transparent)]
 pub struct DispatchDois(pub bool);
 
+/// Whether `Configuration::install`/`Configuration::bibtex` keep going after one top-level
+/// dataset fails (a server that is down, a permanently-broken index, ...) instead of stopping
+/// every other dataset's in-flight work. With this set, a failing dataset is recorded into the
+/// `ActionError::Partial` returned once every dataset has finished rather than propagated
+/// immediately, and reported as it happens via `Message::TaskFailed` so the caller does not have
+/// to wait for the whole run to end to learn about it.
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct ContinueOnError(pub bool);
+
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 pub struct Force(pub bool);
@@ -402,10 +1059,54 @@ pub struct Force(pub bool);
 #[repr(transparent)]
 pub struct Keep(pub bool);
 
+/// Whether `Configuration::install` re-hashes each resource's final on-disk file (after download
+/// and any in-flight or separate decode pass) and compares it against the index's expected digest,
+/// independently of the hash checks `remote::Server`/`decode::decompress` already perform while
+/// writing the bytes. This exists to catch corruption introduced after those checks pass — a
+/// faulty disk, a concurrent edit — and, combined with `Force`, to recover from it automatically
+/// rather than leaving a silently bad file in place.
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct Verify(pub bool);
+
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 pub struct Pretty(pub bool);
 
+/// Which citation export format `Configuration::bibtex` produces, picked from `output_path`'s
+/// extension unless the caller passes one explicitly. Besides picking how `bibtex::write`
+/// serializes the accumulated `doi_to_path_ids_and_content` map, this also selects the `Accept`
+/// header sent to doi.org, so each DOI's content negotiation returns metadata already in the
+/// target format rather than needing to be converted afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitationFormat {
+    BibTex,
+    CslJson,
+    Ris,
+}
+
+impl CitationFormat {
+    /// Recognises the conventional extension for each format; any other extension (including
+    /// none at all) returns `None`, leaving the caller to fall back to `BibTex`.
+    pub fn from_extension(extension: &std::ffi::OsStr) -> Option<CitationFormat> {
+        match extension.to_str()? {
+            "bib" => Some(CitationFormat::BibTex),
+            "json" => Some(CitationFormat::CslJson),
+            "ris" => Some(CitationFormat::Ris),
+            _ => None,
+        }
+    }
+
+    /// The `Accept` header doi.org's content negotiation expects for this format.
+    pub fn accept_header(self) -> &'static str {
+        match self {
+            CitationFormat::BibTex => "application/x-bibtex; charset=utf-8",
+            CitationFormat::CslJson => "application/vnd.citationstyles.csl+json; charset=utf-8",
+            CitationFormat::Ris => "application/x-research-info-systems; charset=utf-8",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 pub struct CalculateSize(pub bool);
@@ -0,0 +1,171 @@
+use crate::configuration;
+use crate::json_index;
+use crate::types;
+use futures::future::FutureExt;
+
+/// The on-disk artifact a resource resolves to, resolved the same way `install_directory` and
+/// `verify_directory` do it, plus the compression suffix its `.download`/bitfield sidecars (if
+/// any are still lying around from an interrupted download) are named after.
+pub struct ResourceArtifact {
+    pub path: std::path::PathBuf,
+    pub suffix: types::Name,
+}
+
+/// Resolves `resource_path_id`'s installed artifact path: the bare path for `Raw` datasets, or
+/// the compressed variant at its suffixed path for non-`Raw` ones (see `plan::classify` and the
+/// hashing branch in `verify_directory` below, which this mirrors). `repair` uses this to find
+/// the right file to delete before re-downloading a mismatch, since deleting the bare path for a
+/// non-`Raw` dataset would be a no-op and leave the corrupt suffixed file in place.
+///
+/// Returns `Ok(None)` if `resource_path_id`'s parent directory has no index, or the index no
+/// longer lists it, since there is then no compression suffix to resolve a path from.
+pub fn resource_artifact(
+    path_root: &types::PathRoot,
+    resource_path_id: &types::PathId,
+    mode: configuration::InstallableMode,
+) -> Result<Option<ResourceArtifact>, types::ActionError> {
+    let Some((parent, name)) = resource_path_id.0.rsplit_once('/') else {
+        return Ok(None);
+    };
+    let index_path_id =
+        types::PathId(parent.to_owned()).join(&types::Name("-index.json".to_owned()));
+    let index = match std::fs::read(path_root.join(&index_path_id)) {
+        Ok(content) => json_index::Index::from_bytes(&content)?,
+        Err(_) => return Ok(None),
+    };
+    let resource = index
+        .files
+        .iter()
+        .map(|file| &file.resource)
+        .chain(
+            index
+                .other_files
+                .iter()
+                .map(|other_file| &other_file.resource),
+        )
+        .find(|resource| resource.name.0 == name);
+    Ok(resource.map(|resource| {
+        let (_, compression_properties) = resource.best_compression();
+        let suffix = compression_properties.suffix.clone();
+        let path = if mode == configuration::InstallableMode::Raw {
+            path_root.join(resource_path_id)
+        } else {
+            path_root.join_with_suffix(resource_path_id, &suffix.0)
+        };
+        ResourceArtifact { path, suffix }
+    }))
+}
+
+/// Recursively walks an already-installed dataset directory using its on-disk index (no remote
+/// calls, unlike `install_directory`/`plan_directory`) and recomputes the hash of every resource
+/// that is present locally, comparing it against the index's expected value. A resource that is
+/// not present at all is reported as a mismatch too, since that is exactly what `repair` needs to
+/// know to re-download it. A dataset with no local index yet (never installed) contributes no
+/// mismatches.
+pub fn verify_directory(
+    running: types::RunControl,
+    sender: tokio::sync::mpsc::UnboundedSender<types::Message>,
+    path_root: types::PathRoot,
+    path_id: types::PathId,
+    mode: configuration::InstallableMode,
+    file_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+) -> std::pin::Pin<
+    std::boxed::Box<
+        dyn futures::future::Future<Output = Result<Vec<types::VerifyMismatch>, types::ActionError>>
+            + Send,
+    >,
+> {
+    async move {
+        let index_path_id = path_id.join(&types::Name("-index.json".to_owned()));
+        let index: json_index::Index = {
+            let _permit = file_semaphore.acquire().await?;
+            match std::fs::read(path_root.join(&index_path_id)) {
+                Ok(content) => json_index::Index::from_bytes(&content)?,
+                Err(_) => return Ok(Vec::new()),
+            }
+        };
+        let mut join_set = tokio::task::JoinSet::new();
+        for directory in &index.directories {
+            let running = running.clone();
+            let sender = sender.clone();
+            let path_root = path_root.clone();
+            let path_id = path_id.join(directory);
+            let file_semaphore = file_semaphore.clone();
+            join_set.spawn(verify_directory(
+                running,
+                sender,
+                path_root,
+                path_id,
+                mode,
+                file_semaphore,
+            ));
+        }
+        let mut mismatches = Vec::new();
+        while let Some(task) = join_set.join_next().await {
+            match task {
+                Ok(result) => mismatches.extend(result?),
+                Err(error) => return Err(types::ActionError::Join(error)),
+            }
+        }
+        if mode != configuration::InstallableMode::Remote {
+            for resource in index
+                .files
+                .iter()
+                .map(|file| &file.resource)
+                .chain(index.other_files.iter().map(|other_file| &other_file.resource))
+            {
+                if running.is_stopped() {
+                    return Ok(mismatches);
+                }
+                running.wait_if_paused().await;
+                let resource_path_id = path_id.join(&resource.name);
+                // `install_directory` keeps the compressed variant on disk for non-`Raw` datasets
+                // (the suffixed path) and only decompresses to the bare path for `Raw` ones, so
+                // the installed artifact must be looked up the same way `plan::classify` does
+                let (resource_path, expected_hash) = if mode == configuration::InstallableMode::Raw
+                {
+                    (path_root.join(&resource_path_id), &resource.hash)
+                } else {
+                    let (_, compression_properties) = resource.best_compression();
+                    (
+                        path_root
+                            .join_with_suffix(&resource_path_id, &compression_properties.suffix.0),
+                        compression_properties.hash,
+                    )
+                };
+                let reason = {
+                    let _permit = file_semaphore.acquire().await?;
+                    match std::fs::File::open(&resource_path) {
+                        Ok(file) => {
+                            match types::Hash::hasher_from_reader(expected_hash.algorithm, file) {
+                                Ok(hasher) => {
+                                    if hasher.finalize() == expected_hash.digest {
+                                        None
+                                    } else {
+                                        Some("checksum mismatch".to_owned())
+                                    }
+                                }
+                                Err(error) => Some(format!("{error:?}")),
+                            }
+                        }
+                        Err(_) => Some("missing".to_owned()),
+                    }
+                };
+                sender
+                    .send(types::Message::VerifyProgress {
+                        path_id: resource_path_id.clone(),
+                        mismatch: reason.clone(),
+                    })
+                    .map_err(|_| types::ActionError::Send(path_id.clone()))?;
+                if let Some(reason) = reason {
+                    mismatches.push(types::VerifyMismatch {
+                        path_id: resource_path_id,
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+    .boxed()
+}
@@ -6,7 +6,7 @@ async fn try_bibtex() -> anyhow::Result<()> {
     )?
     .0
     .bibtex(
-        std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true)),
+        undr::RunControl::new(),
         |message| {
             println!("{:?}", message);
         },
@@ -18,7 +18,10 @@ async fn try_bibtex() -> anyhow::Result<()> {
         std::fs::canonicalize(std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")))?
             .join("tests")
             .join("test.bib"),
+        None,
         undr::Pretty(true),
+        undr::ContinueOnError(false),
+        None,
     )
     .await?;
     Ok(())
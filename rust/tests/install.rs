@@ -7,7 +7,16 @@ async fn try_install() -> anyhow::Result<()> {
     configuration
         .0
         .install(
-            std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true)),
+            undr::InstallControl::new(
+                undr::RunControl::new(),
+                undr::DownloadPermits(32),
+                undr::DecodePermits(
+                    std::thread::available_parallelism()
+                        .unwrap_or(std::num::NonZeroUsize::new(1).unwrap())
+                        .get()
+                        * 2,
+                ),
+            ),
             |message| {
                 println!("{:?}", message);
             },
@@ -15,15 +24,11 @@ async fn try_install() -> anyhow::Result<()> {
             undr::Keep(false),
             undr::DispatchDois(false),
             undr::CalculateSize(false),
+            undr::Verify(true),
+            undr::ContinueOnError(false),
             undr::FilePermits(64),
             undr::DownloadIndexPermits(32),
-            undr::DownloadPermits(32),
-            undr::DecodePermits(
-                std::thread::available_parallelism()
-                    .unwrap_or(std::num::NonZeroUsize::new(1).unwrap())
-                    .get()
-                    * 2,
-            ),
+            None,
         )
         .await?;
     Ok(())